@@ -0,0 +1,61 @@
+//! Parsing and transformation times for inputs that grow in length, in nesting depth and in the
+//! number of operands of a single operator.
+//!
+//! To compare a change with the commit before it, run
+//! `cargo bench -p set --bench transform -- --save-baseline before` on that commit and
+//! `cargo bench -p set --bench transform -- --baseline before` on the change.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use set::SetConfig;
+
+fn conjunction_chain(n: usize) -> String {
+    let mut formula = format!("v{} ⊆ v{}", n - 1, n);
+    for i in (0..n - 1).rev() {
+        formula = format!("(v{i} ⊆ v{} ∧ {formula})", i + 1);
+    }
+    formula
+}
+
+fn nested_term(n: usize) -> String {
+    let mut term = String::from("v1");
+    for _ in 0..n {
+        term = format!("Vereinigung({term})");
+    }
+    format!("v0 ∈ {term}")
+}
+
+fn long_union(n: usize) -> String {
+    let operands = (1..=n).map(|i| format!("v{i}")).collect::<Vec<_>>();
+    format!("v0 ∈ {}", operands.join(" ∪ "))
+}
+
+fn bench(c: &mut Criterion, name: &str, sizes: &[usize], formula: fn(usize) -> String) {
+    let mut group = c.benchmark_group(name);
+    for &n in sizes {
+        let input = formula(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &input, |b, input| {
+            b.iter(|| set::run(input, SetConfig::default()))
+        });
+    }
+    group.finish();
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse long union");
+    for n in [100, 400, 1600] {
+        let input = long_union(n);
+        group.bench_with_input(BenchmarkId::from_parameter(n), &input, |b, input| {
+            b.iter(|| set::parse(input))
+        });
+    }
+    group.finish();
+}
+
+fn transform(c: &mut Criterion) {
+    bench(c, "conjunction chain", &[10, 100, 400], conjunction_chain);
+    bench(c, "nested term", &[10, 50, 100], nested_term);
+    bench(c, "long union", &[10, 100, 400], long_union);
+}
+
+criterion_group!(benches, parse, transform);
+criterion_main!(benches);
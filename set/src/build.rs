@@ -0,0 +1,268 @@
+//! Functions for building syntax trees directly, e.g.
+//! `forall(var("x"), implies(var("x").elem(var("a")), var("x").subset_of(pow(var("b")))))`.
+
+use crate::parser::{
+    variable_index, Connective, Constant, NodeType, Operator, Quantifier, Relation, SyntaxNode,
+};
+
+fn node(entry: NodeType, children: Vec<SyntaxNode>) -> SyntaxNode {
+    SyntaxNode { entry, children }
+}
+
+fn binder(var: &SyntaxNode) {
+    assert!(
+        matches!(var.entry, NodeType::Variable(..)),
+        "Expected variable, found {var}"
+    );
+}
+
+/// `name` is a single letter or `v` followed by a number, as in the input syntax, e.g. `v1` or `v₁`.
+pub fn var(name: &str) -> SyntaxNode {
+    let index = variable_index(name).unwrap_or_else(|| panic!("Invalid variable name '{name}'"));
+    node(NodeType::Variable(index), vec![])
+}
+
+/// The empty set `∅`.
+pub fn empty() -> SyntaxNode {
+    node(NodeType::Constant(Constant::EmptySet), vec![])
+}
+
+/// The set of natural numbers `ω`.
+pub fn omega() -> SyntaxNode {
+    node(NodeType::Constant(Constant::Omega), vec![])
+}
+
+/// The singleton `{a}`.
+pub fn singleton(a: SyntaxNode) -> SyntaxNode {
+    node(NodeType::Operator(Operator::Singleton), vec![a])
+}
+
+/// The unordered pair `{a, b}`.
+pub fn pair(a: SyntaxNode, b: SyntaxNode) -> SyntaxNode {
+    node(NodeType::Operator(Operator::PairSet), vec![a, b])
+}
+
+/// The ordered pair `⟨a, b⟩`.
+pub fn ordered_pair(a: SyntaxNode, b: SyntaxNode) -> SyntaxNode {
+    node(NodeType::Operator(Operator::OrderedPair), vec![a, b])
+}
+
+/// The finite set `{a, b, c, ..}` of more than two elements.
+pub fn enumeration(elements: Vec<SyntaxNode>) -> SyntaxNode {
+    assert!(
+        elements.len() > 2,
+        "Enumerations have more than two elements"
+    );
+    node(NodeType::Operator(Operator::Enumeration), elements)
+}
+
+/// The power set `Pot(a)`.
+pub fn pow(a: SyntaxNode) -> SyntaxNode {
+    node(NodeType::Operator(Operator::PowerSet), vec![a])
+}
+
+/// The union `Vereinigung(a)` of the elements of `a`.
+pub fn big_union(a: SyntaxNode) -> SyntaxNode {
+    node(NodeType::Operator(Operator::BigUnion), vec![a])
+}
+
+/// The intersection `Durchschnitt(a)` of the elements of `a`.
+pub fn big_intersection(a: SyntaxNode) -> SyntaxNode {
+    node(NodeType::Operator(Operator::BigIntersection), vec![a])
+}
+
+/// The set `{var ∈ set | phi}`.
+pub fn comprehension(var: SyntaxNode, set: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(NodeType::Comprehension, vec![var, set, phi])
+}
+
+/// The class `{var | phi}`.
+pub fn class(var: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(NodeType::ClassComprehension, vec![var, phi])
+}
+
+/// The definite description `ιvar phi`.
+pub fn description(var: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(NodeType::Description, vec![var, phi])
+}
+
+/// The negation `¬phi`.
+pub fn not(phi: SyntaxNode) -> SyntaxNode {
+    node(NodeType::Connective(Connective::Negation), vec![phi])
+}
+
+/// The conjunction `(phi ∧ psi)`.
+pub fn and(phi: SyntaxNode, psi: SyntaxNode) -> SyntaxNode {
+    node(
+        NodeType::Connective(Connective::Conjunction),
+        vec![phi, psi],
+    )
+}
+
+/// The disjunction `(phi ∨ psi)`.
+pub fn or(phi: SyntaxNode, psi: SyntaxNode) -> SyntaxNode {
+    node(
+        NodeType::Connective(Connective::Disjunction),
+        vec![phi, psi],
+    )
+}
+
+/// The implication `(phi → psi)`.
+pub fn implies(phi: SyntaxNode, psi: SyntaxNode) -> SyntaxNode {
+    node(
+        NodeType::Connective(Connective::Implication),
+        vec![phi, psi],
+    )
+}
+
+/// The biconditional `(phi ↔ psi)`.
+pub fn iff(phi: SyntaxNode, psi: SyntaxNode) -> SyntaxNode {
+    node(
+        NodeType::Connective(Connective::Biconditional),
+        vec![phi, psi],
+    )
+}
+
+/// The universal quantification `∀var phi`.
+pub fn forall(var: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(NodeType::Quantifier(Quantifier::Universal), vec![var, phi])
+}
+
+/// The existential quantification `∃var phi`.
+pub fn exists(var: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(
+        NodeType::Quantifier(Quantifier::Existential),
+        vec![var, phi],
+    )
+}
+
+/// The unique existential quantification `∃!var phi`.
+pub fn exists_unique(var: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(
+        NodeType::Quantifier(Quantifier::UniqueExistential),
+        vec![var, phi],
+    )
+}
+
+/// The bounded universal quantification `∀var ∈ set phi`.
+pub fn forall_in(var: SyntaxNode, set: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(
+        NodeType::BoundedQuantifier(Quantifier::Universal),
+        vec![var, set, phi],
+    )
+}
+
+/// The bounded existential quantification `∃var ∈ set phi`.
+pub fn exists_in(var: SyntaxNode, set: SyntaxNode, phi: SyntaxNode) -> SyntaxNode {
+    binder(&var);
+    node(
+        NodeType::BoundedQuantifier(Quantifier::Existential),
+        vec![var, set, phi],
+    )
+}
+
+impl SyntaxNode {
+    /// The formula `self ∈ set`.
+    pub fn elem(self, set: SyntaxNode) -> SyntaxNode {
+        node(NodeType::Relation(Relation::Element), vec![self, set])
+    }
+
+    /// The formula `self ∉ set`.
+    pub fn not_elem(self, set: SyntaxNode) -> SyntaxNode {
+        node(NodeType::Relation(Relation::NotElement), vec![self, set])
+    }
+
+    /// The formula `self = other`.
+    pub fn equals(self, other: SyntaxNode) -> SyntaxNode {
+        node(NodeType::Relation(Relation::Equality), vec![self, other])
+    }
+
+    /// The formula `self ≠ other`.
+    pub fn not_equals(self, other: SyntaxNode) -> SyntaxNode {
+        node(NodeType::Relation(Relation::NotEqual), vec![self, other])
+    }
+
+    /// The formula `self ⊆ other`.
+    pub fn subset_of(self, other: SyntaxNode) -> SyntaxNode {
+        node(NodeType::Relation(Relation::Subset), vec![self, other])
+    }
+
+    /// The union `self ∪ other`.
+    pub fn union(self, other: SyntaxNode) -> SyntaxNode {
+        node(NodeType::Operator(Operator::Union), vec![self, other])
+    }
+
+    /// The intersection `self ∩ other`.
+    pub fn intersection(self, other: SyntaxNode) -> SyntaxNode {
+        node(
+            NodeType::Operator(Operator::Intersection),
+            vec![self, other],
+        )
+    }
+
+    /// The difference `self \ other`.
+    pub fn difference(self, other: SyntaxNode) -> SyntaxNode {
+        node(NodeType::Operator(Operator::Difference), vec![self, other])
+    }
+
+    /// The symmetric difference `self △ other`.
+    pub fn symmetric_difference(self, other: SyntaxNode) -> SyntaxNode {
+        node(
+            NodeType::Operator(Operator::SymmetricDifference),
+            vec![self, other],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn parsed(input: &str) -> SyntaxNode {
+        parse(input).unwrap().into_root()
+    }
+
+    #[test]
+    fn built_trees_equal_parsed_ones() {
+        let (x, a, b) = (var("x"), var("a"), var("b"));
+        let formula = forall(
+            x.clone(),
+            implies(
+                x.clone().elem(a.clone()),
+                x.clone().subset_of(pow(b.clone())),
+            ),
+        );
+        assert_eq!(formula, parsed("∀x (x ∈ a → x ⊆ Pot(b))"));
+        let formula = exists_in(
+            x.clone(),
+            a.clone().symmetric_difference(b.clone()),
+            not(x.clone().equals(ordered_pair(a.clone(), empty()))),
+        );
+        assert_eq!(formula, parsed("∃x ∈ a △ b ¬x = ⟨a, ∅⟩"));
+        let formula = x.clone().elem(class(var("y"), var("y").not_elem(var("y"))));
+        assert_eq!(formula, parsed("x ∈ {y | y ∉ y}"));
+        let elements = vec![a.clone(), b.clone(), omega()];
+        assert_eq!(x.elem(enumeration(elements)), parsed("x ∈ {a, b, ω}"));
+        assert_eq!(var("v₁₂"), var("v12"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected variable")]
+    fn binders_must_be_variables() {
+        forall(empty(), var("x").elem(var("y")));
+    }
+
+    #[test]
+    #[should_panic(expected = "Invalid variable name")]
+    fn variable_names_follow_the_input_syntax() {
+        var("xy");
+    }
+}
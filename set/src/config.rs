@@ -0,0 +1,221 @@
+use crate::{Limits, Profile, SetConfig, Strategy, PROFILES};
+use std::collections::BTreeMap;
+
+type Preset = fn() -> SetConfig;
+
+pub const PRESETS: &[(&str, Preset)] = &[
+    ("Eliminate everything", SetConfig::default),
+    ("Primitive ∈ only", SetConfig::primitive),
+    ("Keep operators", SetConfig::keep_operators),
+];
+
+/// Eliminates all defined notation but keeps `=`.
+impl Default for SetConfig {
+    fn default() -> Self {
+        SetConfig {
+            variables: true,
+            bounded_quantifiers: true,
+            unique_existence: true,
+            empty_set: true,
+            omega: true,
+            negated_relations: true,
+            subset: true,
+            singleton: true,
+            comprehension: true,
+            replacement: true,
+            class_comprehension: true,
+            description: true,
+            power_set: true,
+            big_intersection: true,
+            big_union: true,
+            intersection: true,
+            difference: true,
+            symmetric_difference: true,
+            union: true,
+            pair_set: true,
+            enumeration: true,
+            enumeration_as_pairs: false,
+            ordered_pair: true,
+            equality: false,
+            equality_leibniz: false,
+            definitions: BTreeMap::new(),
+            strategy: Strategy::Outermost,
+            profile: PROFILES[0].1,
+            relativization: None,
+            limits: Limits::default(),
+        }
+    }
+}
+
+impl SetConfig {
+    /// Also eliminates `=`, so the result only contains `∈`.
+    pub fn primitive() -> Self {
+        SetConfig::builder().equality(true).build()
+    }
+
+    /// Keeps constants, operators and term-forming notation, but eliminates `⊆` and negated
+    /// relations.
+    pub fn keep_operators() -> Self {
+        SetConfig::builder()
+            .empty_set(false)
+            .omega(false)
+            .singleton(false)
+            .comprehension(false)
+            .replacement(false)
+            .class_comprehension(false)
+            .description(false)
+            .power_set(false)
+            .big_intersection(false)
+            .big_union(false)
+            .intersection(false)
+            .difference(false)
+            .symmetric_difference(false)
+            .union(false)
+            .pair_set(false)
+            .enumeration(false)
+            .ordered_pair(false)
+            .build()
+    }
+
+    /// Starts a [`SetConfigBuilder`] from the default configuration.
+    pub fn builder() -> SetConfigBuilder {
+        SetConfigBuilder {
+            config: SetConfig::default(),
+        }
+    }
+
+    /// Reads a configuration written by [`SetConfig::to_toml`]. Missing fields keep their default,
+    /// unknown fields are an error.
+    #[cfg(feature = "serde")]
+    pub fn from_toml(input: &str) -> anyhow::Result<Self> {
+        Ok(toml::from_str(input)?)
+    }
+
+    /// Writes the configuration as TOML.
+    #[cfg(feature = "serde")]
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        // Going through `Value` moves the tables behind the plain values, as TOML requires.
+        Ok(toml::Value::try_from(self)?.to_string())
+    }
+}
+
+/// Starts from [`SetConfig::default`] or, via `From`, from any other configuration.
+pub struct SetConfigBuilder {
+    config: SetConfig,
+}
+
+macro_rules! switches {
+    ($($field:ident: $doc:literal),*) => {
+        impl SetConfigBuilder {
+            $(
+                #[doc = $doc]
+                pub fn $field(mut self, value: bool) -> Self {
+                    self.config.$field = value;
+                    self
+                }
+            )*
+        }
+    };
+}
+
+switches!(
+    variables: "Whether the variables named by letters are renamed to `v₀`, `v₁`, ….",
+    bounded_quantifiers: "Whether `∀x ∈ y` and `∃x ∈ y` are eliminated.",
+    unique_existence: "Whether `∃!` is eliminated.",
+    empty_set: "Whether `∅` is eliminated.",
+    omega: "Whether `ω` is eliminated.",
+    negated_relations: "Whether `∉` and `≠` are eliminated.",
+    subset: "Whether `⊆` is eliminated.",
+    singleton: "Whether singletons `{x}` are eliminated.",
+    comprehension: "Whether comprehensions `{x ∈ y | φ}` are eliminated.",
+    replacement: "Whether replacements `{t | x ∈ y}` are eliminated.",
+    class_comprehension: "Whether class comprehensions `{x | φ}` are eliminated.",
+    description: "Whether descriptions `ιx φ` are eliminated.",
+    power_set: "Whether `Pot` is eliminated.",
+    big_intersection: "Whether `Durchschnitt` is eliminated.",
+    big_union: "Whether `Vereinigung` is eliminated.",
+    intersection: "Whether `∩` is eliminated.",
+    difference: "Whether `\\` is eliminated.",
+    symmetric_difference: "Whether `△` is eliminated.",
+    union: "Whether `∪` is eliminated.",
+    pair_set: "Whether pair sets `{x, y}` are eliminated.",
+    enumeration: "Whether enumerations `{x, y, z}` are eliminated.",
+    enumeration_as_pairs: "Whether enumerations are eliminated by rewriting them as a union of \
+        pair sets, and a singleton if the count is odd, rather than directly.",
+    ordered_pair: "Whether ordered pairs `(x, y)` are eliminated.",
+    equality: "Whether `=` is eliminated.",
+    equality_leibniz: "Whether `=` is eliminated by the Leibniz definition `∀z (x ∈ z ↔ y ∈ z)` \
+        instead of by extensionality."
+);
+
+impl SetConfigBuilder {
+    /// Whether applications of the defined symbol `name` are unfolded.
+    pub fn definition(mut self, name: &str, unfold: bool) -> Self {
+        self.config.definitions.insert(name.into(), unfold);
+        self
+    }
+
+    /// The order in which subformulas are rewritten.
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.config.strategy = strategy;
+        self
+    }
+
+    /// The definitions used for singletons, ordered pairs and big intersections.
+    pub fn profile(mut self, profile: Profile) -> Self {
+        self.config.profile = profile;
+        self
+    }
+
+    /// Restricts every quantifier of the result to the set `set`.
+    pub fn relativization(mut self, set: &str) -> Self {
+        self.config.relativization = Some(set.into());
+        self
+    }
+
+    /// Bounds on the size of the result and the number of rewrite steps.
+    pub fn limits(mut self, limits: Limits) -> Self {
+        self.config.limits = limits;
+        self
+    }
+
+    /// Finishes the configuration.
+    pub fn build(self) -> SetConfig {
+        self.config
+    }
+}
+
+impl From<SetConfig> for SetConfigBuilder {
+    fn from(config: SetConfig) -> Self {
+        SetConfigBuilder { config }
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use crate::{Limits, SetConfig, Strategy, PROFILES};
+
+    #[test]
+    fn toml_round_trip() {
+        let config = SetConfig::builder()
+            .subset(false)
+            .definition("f", false)
+            .strategy(Strategy::Innermost)
+            .profile(PROFILES[2].1)
+            .relativization("V")
+            .limits(Limits {
+                max_steps: 10,
+                ..Limits::default()
+            })
+            .build();
+        let toml = config.to_toml().unwrap();
+        assert_eq!(SetConfig::from_toml(&toml).unwrap(), config);
+    }
+
+    #[test]
+    fn unknown_fields_are_an_error() {
+        assert!(SetConfig::from_toml("subset = false").is_ok());
+        assert!(SetConfig::from_toml("subst = false").is_err());
+        assert!(SetConfig::from_toml("[limits]\nmax_step = 10").is_err());
+    }
+}
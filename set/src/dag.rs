@@ -0,0 +1,111 @@
+//! Hash-consing for memoising rule application in the outermost strategy.
+//!
+//! This is not a representation of formulas: they are parsed, rewritten and returned as
+//! [`SyntaxNode`] trees, and a result assembled from memoised subterms is expanded into a tree
+//! again, so copies of a subterm are still stored separately in the output. The [`Interner`]
+//! only gives structurally equal subterms a common [`Term`], so that within one transformation
+//! the result of rewriting a subterm is looked up instead of being computed again for every
+//! copy of it. The innermost and single step strategies rewrite without it.
+
+use crate::parser::{NodeType, SyntaxNode};
+use std::collections::HashMap;
+
+#[derive(Debug)]
+struct TermNode {
+    entry: NodeType,
+    children: Vec<Term>,
+    size: usize,
+    depth: usize,
+}
+
+/// A subterm, given as an index into the `Interner` that built it. Structurally equal
+/// subterms of the same `Interner` have the same index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Term(u32);
+
+impl Term {
+    /// The index of the term in its `Interner`.
+    pub fn id(self) -> usize {
+        self.0 as usize
+    }
+}
+
+#[derive(Default)]
+pub struct Interner {
+    nodes: Vec<TermNode>,
+    index: HashMap<(NodeType, Vec<Term>), Term>,
+    shared: bool,
+}
+
+impl Interner {
+    /// An empty `Interner`.
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// The term with the given entry and children, shared with an equal term made before.
+    pub fn make(&mut self, entry: NodeType, children: Vec<Term>) -> Term {
+        let key = (entry.clone(), children);
+        if let Some(term) = self.index.get(&key) {
+            self.shared |= !key.1.is_empty();
+            return *term;
+        }
+        let term = Term(self.nodes.len() as u32);
+        let size = key
+            .1
+            .iter()
+            .fold(1usize, |n, c| n.saturating_add(self.size(*c)));
+        let depth = 1 + key.1.iter().map(|c| self.depth(*c)).max().unwrap_or(0);
+        self.nodes.push(TermNode {
+            entry,
+            children: key.1.clone(),
+            size,
+            depth,
+        });
+        self.index.insert(key, term);
+        term
+    }
+
+    /// The term of a syntax tree, sharing structurally equal subterms.
+    pub fn intern(&mut self, tree: &SyntaxNode) -> Term {
+        let children = tree.children.iter().map(|c| self.intern(c)).collect();
+        self.make(tree.entry.clone(), children)
+    }
+
+    /// The node type at the root of the term.
+    pub fn entry(&self, term: Term) -> NodeType {
+        self.nodes[term.id()].entry.clone()
+    }
+
+    /// The direct subterms of the term.
+    pub fn children(&self, term: Term) -> &[Term] {
+        &self.nodes[term.id()].children
+    }
+
+    /// The number of nodes of the term as a tree, counting shared subterms every time.
+    pub fn size(&self, term: Term) -> usize {
+        self.nodes[term.id()].size
+    }
+
+    /// The number of nodes on a longest path from the term to a leaf.
+    pub fn depth(&self, term: Term) -> usize {
+        self.nodes[term.id()].depth
+    }
+
+    /// Whether a term other than a leaf was made more than once.
+    pub fn has_shared_subterms(&self) -> bool {
+        self.shared
+    }
+
+    /// Expands the term into a syntax tree again.
+    pub fn to_tree(&self, term: Term) -> SyntaxNode {
+        SyntaxNode {
+            entry: self.entry(term),
+            children: self
+                .children(term)
+                .iter()
+                .map(|c| self.to_tree(*c))
+                .collect(),
+        }
+    }
+}
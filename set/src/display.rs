@@ -1,44 +1,91 @@
 use crate::parser::{Connective, Constant, NodeType, Operator, Quantifier, Relation, SyntaxNode};
-use std::fmt;
+use std::{cell::RefCell, collections::HashMap, fmt, ops::Range};
+
+thread_local! {static MARKS: RefCell<Option<Marks>> = const { RefCell::new(None) }}
+
+const START: char = '\u{E000}';
+const END: char = '\u{E001}';
+
+/// Set while [`SyntaxNode::to_string_with_spans`] renders a formula. `index` maps each node to
+/// its position in preorder, `order` records the nodes in the order they are printed.
+struct Marks {
+    index: HashMap<*const SyntaxNode, usize>,
+    order: Vec<usize>,
+}
+
+/// The name of the variable with index `index`, as it is displayed and parsed.
+pub(crate) fn variable_name(index: u32) -> String {
+    if (index < u32::MAX - 55 && index > u32::MAX - 91)
+        || (index < u32::MAX - 96 && index > u32::MAX - 123)
+    {
+        return char::from_u32(u32::MAX - index).unwrap().to_string();
+    }
+    let subscript = |d: char| char::from_u32('₀' as u32 + d.to_digit(10).unwrap()).unwrap();
+    std::iter::once('v')
+        .chain(index.to_string().chars().map(subscript))
+        .collect()
+}
 
 impl fmt::Display for SyntaxNode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match self.entry {
+        let marked = MARKS.with(|rc| match rc.borrow_mut().as_mut() {
+            Some(marks) => match marks.index.get(&(self as *const SyntaxNode)) {
+                Some(&i) => {
+                    marks.order.push(i);
+                    true
+                }
+                None => false,
+            },
+            None => false,
+        });
+        if !marked {
+            return self.fmt_node(f);
+        }
+        write!(f, "{START}")?;
+        self.fmt_node(f)?;
+        write!(f, "{END}")
+    }
+}
+
+impl SyntaxNode {
+    fn fmt_node(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.entry {
             NodeType::Constant(c) => match c {
                 Constant::EmptySet => write!(f, "∅"),
                 Constant::Omega => write!(f, "ω"),
             },
-            NodeType::Variable(v) => {
-                if (v < u32::MAX - 55 && v > u32::MAX - 91)
-                    || (v < u32::MAX - 96 && v > u32::MAX - 123)
-                {
-                    let ascii = char::from_u32(u32::MAX - v).unwrap();
-                    write!(f, "{ascii}")
-                } else {
-                    let mut var = String::from("v");
-                    for c in v.to_string().chars() {
-                        match c {
-                            '0' => var.push('₀'),
-                            '1' => var.push('₁'),
-                            '2' => var.push('₂'),
-                            '3' => var.push('₃'),
-                            '4' => var.push('₄'),
-                            '5' => var.push('₅'),
-                            '6' => var.push('₆'),
-                            '7' => var.push('₇'),
-                            '8' => var.push('₈'),
-                            '9' => var.push('₉'),
-                            _ => (),
-                        }
-                    }
-                    write!(f, "{var}")
-                }
-            }
+            NodeType::Variable(v) => write!(f, "{}", variable_name(*v)),
             NodeType::Comprehension => write!(
                 f,
                 "{{{} ∈ {} | {}}}",
                 self.children[0], self.children[1], self.children[2]
             ),
+            NodeType::Definition(name) => {
+                let (params, rest) = self.children.split_at(self.children.len() - 2);
+                write!(f, "def {name}(")?;
+                SyntaxNode::fmt_list(f, params, ", ")?;
+                write!(f, ") := {}; {}", rest[0], rest[1])
+            }
+            NodeType::Operation(name) | NodeType::Predicate(name) => {
+                write!(f, "{name}(")?;
+                SyntaxNode::fmt_list(f, &self.children, ", ")?;
+                write!(f, ")")
+            }
+            NodeType::Relativization => write!(f, "({})^{}", self.children[0], self.children[1]),
+            NodeType::Description => write!(f, "ι{} {}", self.children[0], self.children[1]),
+            NodeType::ClassComprehension => {
+                write!(f, "{{{} | {}}}", self.children[0], self.children[1])
+            }
+            NodeType::Replacement => {
+                write!(f, "{{{} | ", self.children[0])?;
+                for (i, binding) in self.children[1..].chunks(2).enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{} ∈ {}", binding[0], binding[1])?;
+                }
+                write!(f, "}}")
+            }
             NodeType::Relation(r) => match r {
                 Relation::Equality => write!(f, "{} = {}", self.children[0], self.children[1]),
                 Relation::Element => write!(f, "{} ∈ {}", self.children[0], self.children[1]),
@@ -52,10 +99,21 @@ impl fmt::Display for SyntaxNode {
                 Operator::PowerSet => write!(f, "Pot({})", self.children[0]),
                 Operator::BigUnion => write!(f, "Vereinigung({})", self.children[0]),
                 Operator::BigIntersection => write!(f, "Durchschnitt({})", self.children[0]),
-                Operator::Union => write!(f, "{} ∪ {}", self.children[0], self.children[1]),
-                Operator::Intersection => write!(f, "{} ∩ {}", self.children[0], self.children[1]),
+                Operator::Union => self.fmt_operands(f, " ∪ "),
+                Operator::Intersection => self.fmt_operands(f, " ∩ "),
                 Operator::Difference => write!(f, "{} \\ {}", self.children[0], self.children[1]),
+                Operator::OrderedPair => {
+                    write!(f, "⟨{} , {}⟩", self.children[0], self.children[1])
+                }
+                Operator::SymmetricDifference => {
+                    write!(f, "{} △ {}", self.children[0], self.children[1])
+                }
                 Operator::PairSet => write!(f, "{{{} , {}}}", self.children[0], self.children[1]),
+                Operator::Enumeration => {
+                    write!(f, "{{")?;
+                    self.fmt_operands(f, " , ")?;
+                    write!(f, "}}")
+                }
             },
             NodeType::Connective(c) => match c {
                 Connective::Negation => write!(f, "¬{}", self.children[0]),
@@ -75,7 +133,157 @@ impl fmt::Display for SyntaxNode {
             NodeType::Quantifier(q) => match q {
                 Quantifier::Universal => write!(f, "∀{} {}", self.children[0], self.children[1]),
                 Quantifier::Existential => write!(f, "∃{} {}", self.children[0], self.children[1]),
+                Quantifier::UniqueExistential => {
+                    write!(f, "∃!{} {}", self.children[0], self.children[1])
+                }
             },
+            NodeType::BoundedQuantifier(q) => {
+                match q {
+                    Quantifier::Universal => write!(f, "∀{}", self.children[0])?,
+                    Quantifier::Existential => write!(f, "∃{}", self.children[0])?,
+                    Quantifier::UniqueExistential => write!(f, "∃!{}", self.children[0])?,
+                }
+                // Quantifiers are only merged if the set does not mention an earlier binder, which
+                // would capture the set in the inner quantifiers when reading it back.
+                let set = &self.children[1];
+                let mut binders = vec![&self.children[0]];
+                let mut formula = &self.children[2];
+                while formula.entry == self.entry
+                    && formula.children[1] == *set
+                    && !binders.iter().any(|binder| set.mentions(binder))
+                {
+                    write!(f, ",{}", formula.children[0])?;
+                    binders.push(&formula.children[0]);
+                    formula = &formula.children[2];
+                }
+                write!(f, " ∈ {} {}", self.children[1], formula)
+            }
+        }
+    }
+}
+
+impl SyntaxNode {
+    /// Renders the formula like `to_string` and returns the byte range of every node in the
+    /// result, in preorder. Nodes that are not printed on their own, such as the inner
+    /// quantifier of `∀x,y ∈ a φ`, get the range covered by their children, if any.
+    pub fn to_string_with_spans(&self) -> (String, Vec<Option<Range<usize>>>) {
+        let mut index = HashMap::new();
+        self.number(&mut index);
+        let count = index.len();
+        let marks = Marks {
+            index,
+            order: vec![],
+        };
+        MARKS.with(|rc| rc.replace(Some(marks)));
+        let marked = self.to_string();
+        let order = MARKS.with(|rc| rc.take()).unwrap().order;
+        let mut spans = vec![None; count];
+        let mut text = String::new();
+        let mut open = vec![];
+        let mut order = order.into_iter();
+        for c in marked.chars() {
+            match c {
+                START => open.push((order.next().unwrap(), text.len())),
+                END => {
+                    let (i, start) = open.pop().unwrap();
+                    spans[i] = Some(start..text.len());
+                }
+                c => text.push(c),
+            }
+        }
+        self.fill_spans(&mut 0, &mut spans);
+        (text, spans)
+    }
+
+    fn number(&self, index: &mut HashMap<*const SyntaxNode, usize>) {
+        index.insert(self as *const SyntaxNode, index.len());
+        for child in &self.children {
+            child.number(index);
+        }
+    }
+
+    fn fill_spans(&self, next: &mut usize, spans: &mut [Option<Range<usize>>]) {
+        let i = *next;
+        *next += 1;
+        let first = *next;
+        for child in &self.children {
+            child.fill_spans(next, spans);
+        }
+        if spans[i].is_none() {
+            let covered = spans[first..*next].iter().flatten();
+            let start = covered.clone().map(|span| span.start).min();
+            let end = covered.map(|span| span.end).max();
+            spans[i] = start.zip(end).map(|(start, end)| start..end);
+        }
+    }
+
+    fn fmt_operands(&self, f: &mut fmt::Formatter<'_>, separator: &str) -> fmt::Result {
+        SyntaxNode::fmt_list(f, &self.children, separator)
+    }
+
+    fn fmt_list(f: &mut fmt::Formatter<'_>, nodes: &[SyntaxNode], separator: &str) -> fmt::Result {
+        for (i, node) in nodes.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{separator}")?;
+            }
+            write!(f, "{node}")?;
+        }
+        Ok(())
+    }
+}
+
+const LATEX: &[(char, &str)] = &[
+    ('∅', "\\emptyset"),
+    ('ω', "\\omega"),
+    ('∈', "\\in"),
+    ('∉', "\\notin"),
+    ('⊆', "\\subseteq"),
+    ('⊈', "\\nsubseteq"),
+    ('≠', "\\neq"),
+    ('¬', "\\lnot "),
+    ('∧', "\\land"),
+    ('∨', "\\lor"),
+    ('→', "\\rightarrow"),
+    ('↔', "\\leftrightarrow"),
+    ('∀', "\\forall "),
+    ('∃', "\\exists "),
+    ('ι', "\\iota "),
+    ('∪', "\\cup"),
+    ('∩', "\\cap"),
+    ('△', "\\triangle"),
+    ('\\', "\\setminus"),
+    ('⟨', "\\langle "),
+    ('⟩', "\\rangle"),
+    ('{', "\\{"),
+    ('}', "\\}"),
+    ('|', "\\mid"),
+];
+
+impl SyntaxNode {
+    /// Renders the formula as LaTeX math.
+    pub fn to_latex(&self) -> String {
+        let mut result = String::new();
+        let mut subscript = false;
+        for c in self.to_string().chars() {
+            let digit = ('₀'..='₉').contains(&c);
+            match (digit, subscript) {
+                (true, false) => result.push_str("_{"),
+                (false, true) => result.push('}'),
+                _ => (),
+            }
+            subscript = digit;
+            match LATEX.iter().find(|(u, _)| *u == c) {
+                Some((_, latex)) => result.push_str(latex),
+                None if digit => result.push(char::from_digit(c as u32 - '₀' as u32, 10).unwrap()),
+                None => result.push(c),
+            }
+        }
+        if subscript {
+            result.push('}');
         }
+        result
+            .replace("Pot(", "\\mathcal{P}(")
+            .replace("Vereinigung(", "\\bigcup(")
+            .replace("Durchschnitt(", "\\bigcap(")
     }
 }
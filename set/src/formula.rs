@@ -0,0 +1,196 @@
+use crate::{
+    levy::Classification, lexer, limits::LimitExceeded, metrics::Metrics, parser, transformer,
+    NodeType, SetConfig, SyntaxNode, TreeOptions,
+};
+use std::fmt;
+
+/// A formula of set theory as produced by [`parse`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formula {
+    root: SyntaxNode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Parse(ParseError),
+    LimitExceeded(LimitExceeded),
+}
+
+/// Parses a formula, e.g. `∀x ∈ y x ⊆ Pot(y)`.
+pub fn parse(input: &str) -> Result<Formula, ParseError> {
+    let root = lexer::tokanize(input.into())
+        .and_then(parser::parse)
+        .map_err(ParseError::from)?;
+    Ok(Formula { root })
+}
+
+/// Parses a set term such as `Pot(x) ∪ y` rather than a formula.
+pub fn parse_term(input: &str) -> Result<SyntaxNode, ParseError> {
+    lexer::tokanize(input.into())
+        .and_then(parser::parse_term)
+        .map_err(ParseError::from)
+}
+
+impl Formula {
+    /// Wraps a syntax tree, e.g. one made with the functions in [`crate::build`].
+    pub fn new(root: SyntaxNode) -> Self {
+        Formula { root }
+    }
+
+    /// The syntax tree of the formula.
+    pub fn root(&self) -> &SyntaxNode {
+        &self.root
+    }
+
+    /// Turns the formula into its syntax tree.
+    pub fn into_root(self) -> SyntaxNode {
+        self.root
+    }
+
+    /// Applies the transformations enabled in `config`, including the relativization to
+    /// `config.relativization` if it is set.
+    pub fn transform(&self, config: &SetConfig) -> Result<Formula, Error> {
+        Ok(self.transform_with_warnings(config)?.0)
+    }
+
+    /// Like [`Formula::transform`], but also returns the warnings issued while transforming.
+    pub fn transform_with_warnings(
+        &self,
+        config: &SetConfig,
+    ) -> Result<(Formula, Vec<String>), Error> {
+        let mut root = self.root.clone();
+        if let Some(set) = &config.relativization {
+            root = SyntaxNode {
+                entry: NodeType::Relativization,
+                children: vec![root, parse_term(set)?],
+            };
+        }
+        let root = root.transform(config)?;
+        Ok((Formula { root }, transformer::take_warnings()))
+    }
+
+    /// See [`SyntaxNode::relativize`].
+    pub fn relativize(&self, set: &SyntaxNode) -> Formula {
+        Formula {
+            root: self.root.clone().relativize(set),
+        }
+    }
+
+    /// See [`SyntaxNode::unfold_at`].
+    pub fn unfold_at(&self, path: &[usize], config: &SetConfig) -> Option<Formula> {
+        let root = self.root.clone().unfold_at(path, config)?;
+        Some(Formula { root })
+    }
+
+    /// The position of the formula in the Lévy hierarchy.
+    pub fn classify(&self) -> Classification {
+        self.root.classify()
+    }
+
+    /// Size and complexity measures of the formula.
+    pub fn metrics(&self) -> Metrics {
+        self.root.metrics()
+    }
+
+    /// See [`SyntaxNode::to_latex`].
+    pub fn to_latex(&self) -> String {
+        self.root.to_latex()
+    }
+
+    /// See [`SyntaxNode::to_dot`].
+    pub fn to_dot(&self, options: &TreeOptions) -> String {
+        self.root.to_dot(options)
+    }
+
+    /// See [`SyntaxNode::to_ascii_tree`].
+    pub fn to_ascii_tree(&self, options: &TreeOptions) -> String {
+        self.root.to_ascii_tree(options)
+    }
+}
+
+impl fmt::Display for Formula {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.root)
+    }
+}
+
+impl From<anyhow::Error> for ParseError {
+    fn from(error: anyhow::Error) -> Self {
+        ParseError {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for Error {
+    fn from(error: ParseError) -> Self {
+        Error::Parse(error)
+    }
+}
+
+impl From<LimitExceeded> for Error {
+    fn from(error: LimitExceeded) -> Self {
+        Error::LimitExceeded(error)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "{e}"),
+            Error::LimitExceeded(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_term, Error, Formula};
+    use crate::SetConfig;
+
+    #[test]
+    fn errors_are_returned_not_printed() {
+        assert_eq!(
+            parse("x ∈").unwrap_err().to_string(),
+            "Unexpected end of input"
+        );
+        assert!(parse_term("x ∈ y").is_err());
+        let config = SetConfig::builder().relativization("∈").build();
+        let result = parse("∃x x ∈ y").unwrap().transform(&config);
+        assert!(matches!(result, Err(Error::Parse(..))));
+    }
+
+    #[test]
+    fn transform_relativizes_to_the_configured_set() {
+        let config = SetConfig::builder()
+            .variables(false)
+            .relativization("M")
+            .build();
+        let output = parse("∃x x ∈ y").unwrap().transform(&config).unwrap();
+        assert_eq!(output.to_string(), "∃x (x ∈ M ∧ x ∈ y)");
+    }
+
+    #[test]
+    fn formulas_wrap_their_syntax_tree() {
+        let formula = parse("x ⊆ y").unwrap();
+        assert_eq!(Formula::new(formula.root().clone()), formula);
+        let term = parse_term("Pot(x) ∪ y").unwrap();
+        assert_eq!(term.to_string(), "Pot(x) ∪ y");
+        assert_eq!(formula.into_root().children.len(), 2);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::parser::{Connective, NodeType, Quantifier, Relation, SyntaxNode};
+use std::fmt;
+
+pub struct Classification {
+    pub sigma: usize,
+    pub pi: usize,
+    pub unbounded: Vec<String>,
+}
+
+impl Classification {
+    /// Whether every quantifier is bounded.
+    pub fn is_delta_zero(&self) -> bool {
+        self.sigma == 0 && self.pi == 0
+    }
+}
+
+impl fmt::Display for Classification {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let subscript = |n: usize| {
+            n.to_string()
+                .chars()
+                .map(|d| char::from_u32('₀' as u32 + d.to_digit(10).unwrap()).unwrap())
+                .collect::<String>()
+        };
+        match self.sigma.cmp(&self.pi) {
+            _ if self.is_delta_zero() => write!(f, "Δ₀"),
+            std::cmp::Ordering::Less => write!(f, "Σ{}", subscript(self.sigma)),
+            std::cmp::Ordering::Greater => write!(f, "Π{}", subscript(self.pi)),
+            // Both are upper bounds, which does not make the formula Δₙ.
+            std::cmp::Ordering::Equal => {
+                let n = subscript(self.sigma);
+                write!(f, "Σ{n} ∩ Π{n}")
+            }
+        }?;
+        if !self.unbounded.is_empty() {
+            write!(f, " (unbounded: {})", self.unbounded.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+impl SyntaxNode {
+    /// The position of the formula in the Lévy hierarchy, with its unbounded quantifiers.
+    pub fn classify(&self) -> Classification {
+        let mut unbounded = vec![];
+        let (sigma, pi) = self.levels(&mut unbounded);
+        Classification {
+            sigma,
+            pi,
+            unbounded,
+        }
+    }
+
+    fn levels(&self, unbounded: &mut Vec<String>) -> (usize, usize) {
+        match self.entry {
+            NodeType::Connective(Connective::Negation) => {
+                let (sigma, pi) = self.children[0].levels(unbounded);
+                (pi, sigma)
+            }
+            NodeType::Connective(c) => {
+                let (s0, p0) = self.children[0].levels(unbounded);
+                let (s1, p1) = self.children[1].levels(unbounded);
+                match c {
+                    Connective::Implication => (p0.max(s1), s0.max(p1)),
+                    Connective::Biconditional => {
+                        let level = s0.max(p0).max(s1).max(p1);
+                        (level, level)
+                    }
+                    _ => (s0.max(s1), p0.max(p1)),
+                }
+            }
+            NodeType::Quantifier(q) | NodeType::BoundedQuantifier(q) => {
+                let phi = self.children.last().unwrap();
+                if self.is_bounded() {
+                    let (sigma, pi) = phi.levels(unbounded);
+                    return match (&self.entry, q) {
+                        (NodeType::BoundedQuantifier(..), Quantifier::UniqueExistential) => {
+                            (sigma.max(pi), sigma.max(pi))
+                        }
+                        _ => (sigma, pi),
+                    };
+                }
+                unbounded.push(match q {
+                    Quantifier::Universal => format!("∀{}", self.children[0]),
+                    Quantifier::Existential => format!("∃{}", self.children[0]),
+                    Quantifier::UniqueExistential => format!("∃!{}", self.children[0]),
+                });
+                let (sigma, pi) = phi.levels(unbounded);
+                match q {
+                    Quantifier::Universal => SyntaxNode::universal(sigma, pi),
+                    Quantifier::Existential => SyntaxNode::existential(sigma, pi),
+                    Quantifier::UniqueExistential => {
+                        let (s1, p1) = SyntaxNode::universal(pi, sigma);
+                        SyntaxNode::existential(sigma.max(s1), pi.max(p1))
+                    }
+                }
+            }
+            NodeType::Definition(..) => self.children.last().unwrap().levels(unbounded),
+            _ => (0, 0),
+        }
+    }
+
+    fn universal(sigma: usize, pi: usize) -> (usize, usize) {
+        let pi = pi.max(1).min(sigma + 1);
+        (pi + 1, pi)
+    }
+
+    fn existential(sigma: usize, pi: usize) -> (usize, usize) {
+        let sigma = sigma.max(1).min(pi + 1);
+        (sigma, sigma + 1)
+    }
+
+    /// Whether the quantifier is bounded by a variable. Other bounds such as `{y | y = y}` can
+    /// hide unbounded quantifiers, so they do not count.
+    fn is_bounded(&self) -> bool {
+        if let NodeType::BoundedQuantifier(..) = self.entry {
+            return matches!(self.children[1].entry, NodeType::Variable(..));
+        }
+        let NodeType::Variable(var) = self.children[0].entry else {unreachable!()};
+        match self.entry {
+            NodeType::Quantifier(Quantifier::Universal) => {
+                self.children[1].bounds(var, Quantifier::Universal, &mut vec![])
+            }
+            NodeType::Quantifier(Quantifier::Existential) => {
+                self.children[1].bounds(var, Quantifier::Existential, &mut vec![])
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether `self`, the scope of the quantifier `q` over `var`, restricts `var` as in
+    /// `∀x (x ∈ a → φ)` or `∃x (x ∈ a ∧ φ)`. Quantifiers of the same kind in between are looked
+    /// through, as in `∀x ∀y (x ∈ a → …)` or `∀x (φ → ∀y (x ∈ a → …))`, and their variables are
+    /// collected in `inner`, which cannot bound `var`.
+    fn bounds(&self, var: u32, q: Quantifier, inner: &mut Vec<u32>) -> bool {
+        match self.entry {
+            NodeType::Quantifier(inner_q) if inner_q == q => {
+                let NodeType::Variable(v) = self.children[0].entry else {unreachable!()};
+                if v == var {
+                    return false;
+                }
+                inner.push(v);
+                let result = self.children[1].bounds(var, q, inner);
+                inner.pop();
+                result
+            }
+            NodeType::Connective(Connective::Implication) if q == Quantifier::Universal => {
+                self.children[0].restricts(var, inner) || self.children[1].bounds(var, q, inner)
+            }
+            NodeType::Connective(Connective::Biconditional) if q == Quantifier::Universal => {
+                self.children[0].restricts(var, inner) && self.children[1].restricts(var, inner)
+            }
+            NodeType::Connective(Connective::Conjunction) if q == Quantifier::Existential => {
+                self.children[0].bounds(var, q, inner) || self.children[1].bounds(var, q, inner)
+            }
+            _ if q == Quantifier::Existential => self.restricts(var, inner),
+            _ => false,
+        }
+    }
+
+    /// Whether `self` implies `var ∈ t` or `var ⊆ t` for a variable `t` other than `var` and
+    /// the variables in `inner`.
+    fn restricts(&self, var: u32, inner: &[u32]) -> bool {
+        match self.entry {
+            NodeType::Relation(Relation::Element | Relation::Subset) => {
+                let bound = |t| t != var && !inner.contains(&t);
+                matches!(self.children[0].entry, NodeType::Variable(v) if v == var)
+                    && matches!(self.children[1].entry, NodeType::Variable(t) if bound(t))
+            }
+            NodeType::Connective(Connective::Conjunction) => {
+                self.children[0].restricts(var, inner) || self.children[1].restricts(var, inner)
+            }
+            NodeType::Connective(Connective::Disjunction) => {
+                self.children[0].restricts(var, inner) && self.children[1].restricts(var, inner)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parse;
+
+    fn classify(input: &str) -> String {
+        parse(input).unwrap().classify().to_string()
+    }
+
+    #[test]
+    fn only_variables_bound_quantifiers() {
+        assert_eq!(classify("∀x ∈ y x = x"), "Δ₀");
+        assert_eq!(classify("∀x (x ∈ y → x = x)"), "Δ₀");
+        assert_eq!(classify("∀x ∈ {y | y = y} x = x"), "Π₁ (unbounded: ∀x)");
+        assert_eq!(classify("∃x (x ∈ Pot(y) ∧ x = x)"), "Σ₁ (unbounded: ∃x)");
+    }
+
+    #[test]
+    fn only_element_and_subset_bound() {
+        assert_eq!(classify("∃x (x = y ∧ x ∈ x)"), "Σ₁ (unbounded: ∃x)");
+        assert_eq!(classify("∀x (x ⊆ y → x ∈ z)"), "Δ₀");
+        assert_eq!(classify("∀x (x ∈ x → x = x)"), "Π₁ (unbounded: ∀x)");
+    }
+
+    #[test]
+    fn quantifier_prefixes_are_looked_through() {
+        assert_eq!(classify("∀x (x ∈ a → ∀y (y ∈ x → y = y))"), "Δ₀");
+        assert_eq!(classify("∀x ∀y (x ∈ a → (y ∈ b → x = y))"), "Δ₀");
+        assert_eq!(classify("∃x ∃y (x ∈ a ∧ (y ∈ b ∧ x = y))"), "Δ₀");
+        assert_eq!(
+            classify("∀x (x = x → ∀y (x ∈ a → y ∈ x))"),
+            "Π₁ (unbounded: ∀y)"
+        );
+        assert_eq!(classify("∀x ∀y (x ∈ y → x = x)"), "Π₁ (unbounded: ∀x, ∀y)");
+    }
+
+    #[test]
+    fn equal_levels_are_not_called_delta() {
+        assert_eq!(classify("(∀x x ∈ a ↔ y ∈ b)"), "Σ₂ ∩ Π₂ (unbounded: ∀x)");
+    }
+}
@@ -1,4 +1,5 @@
-use anyhow::{bail, Result};
+use anyhow::{bail, ensure, Result};
+use std::cmp::Reverse;
 
 #[derive(Debug, Clone)]
 pub enum Token {
@@ -6,10 +7,13 @@ pub enum Token {
     Rel(String),
     Conn(String),
     Quan(String),
+    Desc(String),
     UnOp(String),
     BinOp(String),
     Var(String),
     Const(String),
+    Def(String),
+    Symbol(String),
 }
 
 const REL: &[&str] = &[
@@ -43,78 +47,112 @@ const CONN: &[&str] = &[
     "\\rightarrow",
     "\\leftrightarrow",
 ];
-const BRACK: &[&str] = &["(", ")", "{", "}", "|", ","];
+const BRACK: &[&str] = &[
+    "(", ")", "{", "}", "⟨", "⟩", "\\langle", "\\rangle", "|", ",", ":=", ";", "^",
+];
 const CONST: &[&str] = &["0", "∅", "\\emptyset", "ω", "\\omega"];
-const QUAN: &[&str] = &["∀", "∃", "\\forall", "\\exists"];
+const QUAN: &[&str] = &["∀", "∃!", "∃", "\\forall", "\\exists!", "\\exists"];
+const DESC: &[&str] = &["ι", "\\iota"];
 const UNOP: &[&str] = &["Pot", "Vereinigung", "\\bigcup", "Durchschnitt", "\\bigcap"];
-const BINOP: &[&str] = &["∪", "\\cup", "∩", "\\cap", "\\"];
+const BINOP: &[&str] = &["∪", "\\cup", "∩", "\\cap", "△", "\\triangle", "\\"];
 
-pub fn tokanize(mut input: String) -> Result<Vec<Token>> {
+pub fn tokanize(input: String) -> Result<Vec<Token>> {
     let mut result = vec![];
-    input = input.split_whitespace().collect();
+    let mut symbols = Vec::<String>::new();
+    let input = input.split_whitespace().collect::<String>();
+    let mut input = input.as_str();
     'outer: while !input.is_empty() {
+        let statement_start = match result.last() {
+            Some(Token::Brack(b)) => b == ";",
+            Some(..) => false,
+            None => true,
+        };
+        if statement_start && input.starts_with("def") {
+            result.push(Token::Def(take(&mut input, "def".len())));
+            let name = input
+                .chars()
+                .take_while(|c| c.is_alphanumeric())
+                .collect::<String>();
+            ensure!(!name.is_empty(), "Missing name of definition");
+            result.push(Token::Symbol(take(&mut input, name.len())));
+            symbols.push(name);
+            symbols.sort_by_key(|s| Reverse(s.len()));
+            continue 'outer;
+        }
+        for x in &symbols {
+            if input.starts_with(x.as_str()) && input[x.len()..].starts_with('(') {
+                result.push(Token::Symbol(take(&mut input, x.len())));
+                continue 'outer;
+            }
+        }
         for x in REL {
             if input.starts_with(x) {
-                result.push(Token::Rel(input.drain(..x.len()).collect()));
+                result.push(Token::Rel(take(&mut input, x.len())));
                 continue 'outer;
             }
         }
         for x in CONN {
             if input.starts_with(x) {
-                result.push(Token::Conn(input.drain(..x.len()).collect()));
+                result.push(Token::Conn(take(&mut input, x.len())));
                 continue 'outer;
             }
         }
         for x in QUAN {
             if input.starts_with(x) {
-                result.push(Token::Quan(input.drain(..x.len()).collect()));
+                result.push(Token::Quan(take(&mut input, x.len())));
+                continue 'outer;
+            }
+        }
+        for x in DESC {
+            if input.starts_with(x) {
+                result.push(Token::Desc(take(&mut input, x.len())));
                 continue 'outer;
             }
         }
         for x in BRACK {
             if input.starts_with(x) {
-                result.push(Token::Brack(input.drain(..x.len()).collect()));
+                result.push(Token::Brack(take(&mut input, x.len())));
                 continue 'outer;
             }
         }
         for x in CONST {
             if input.starts_with(x) {
-                result.push(Token::Const(input.drain(..x.len()).collect()));
+                result.push(Token::Const(take(&mut input, x.len())));
                 continue 'outer;
             }
         }
         for x in UNOP {
             if input.starts_with(x) {
-                result.push(Token::UnOp(input.drain(..x.len()).collect()));
+                result.push(Token::UnOp(take(&mut input, x.len())));
                 continue 'outer;
             }
         }
         for x in BINOP {
             if input.starts_with(x) {
-                result.push(Token::BinOp(input.drain(..x.len()).collect()));
+                result.push(Token::BinOp(take(&mut input, x.len())));
                 continue 'outer;
             }
         }
         if input.starts_with('v') {
-            let mut var_str: String = input.remove(0).into();
-            while !input.is_empty() {
-                if input.chars().next().unwrap().is_numeric() {
-                    var_str.push(input.remove(0));
-                } else {
-                    break;
-                }
-            }
-            result.push(Token::Var(var_str));
+            let digits = input[1..]
+                .chars()
+                .take_while(|c| c.is_ascii_digit() || ('₀'..='₉').contains(c));
+            let len = 1 + digits.map(char::len_utf8).sum::<usize>();
+            result.push(Token::Var(take(&mut input, len)));
             continue 'outer;
         }
-        if input.chars().next().unwrap().is_alphabetic() {
-            result.push(Token::Var(input.remove(0).into()));
+        let c = input.chars().next().unwrap();
+        if c.is_alphabetic() {
+            result.push(Token::Var(take(&mut input, c.len_utf8())));
             continue 'outer;
         }
-        bail!(
-            "Unexpected character '{}' in input string.",
-            input.chars().next().unwrap()
-        );
+        bail!("Unexpected character '{}' in input string.", c);
     }
     Ok(result)
 }
+
+fn take(input: &mut &str, len: usize) -> String {
+    let (token, rest) = input.split_at(len);
+    *input = rest;
+    token.into()
+}
@@ -1,32 +1,219 @@
+//! Transforms formulas of set theory into the language of ∈ and =.
+//!
+//! [`parse`] turns a string into a [`Formula`], which [`Formula::transform`] rewrites according
+//! to a [`SetConfig`]. The syntax tree of a formula is available through [`Formula::root`], and
+//! a formula can be built from a [`SyntaxNode`], e.g. one made with the functions in [`build`],
+//! with [`Formula::new`]. [`run`] combines these steps for callers that only need the printed
+//! result.
+
+pub mod build;
+mod config;
+mod dag;
 mod display;
+mod formula;
+mod levy;
 mod lexer;
+mod limits;
+mod metrics;
 mod parser;
+#[cfg(feature = "serde")]
+mod serial;
 mod transformer;
+mod tree;
+
+pub use config::{SetConfigBuilder, PRESETS};
+pub use formula::{parse, parse_term, Error, Formula, ParseError};
+pub use levy::Classification;
+pub use limits::{Limit, LimitExceeded, Limits};
+pub use metrics::Metrics;
+pub use parser::{Connective, Constant, NodeType, Operator, Quantifier, Relation, SyntaxNode};
+pub use transformer::{register_rule, rule_names, take_warnings, Rule};
+pub use tree::TreeOptions;
+
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Strategy {
+    Outermost,
+    Innermost,
+    SingleStep,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum SingletonDefinition {
+    Comprehension,
+    Pairing,
+    Direct,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum OrderedPairDefinition {
+    Kuratowski,
+    Wiener,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum BigIntersectionDefinition {
+    Undefined,
+    EmptySet,
+    Universe,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(deny_unknown_fields)
+)]
+pub struct Profile {
+    pub singleton: SingletonDefinition,
+    pub ordered_pair: OrderedPairDefinition,
+    pub big_intersection: BigIntersectionDefinition,
+}
+
+pub const PROFILES: &[(&str, Profile)] = &[
+    (
+        "Standard",
+        Profile {
+            singleton: SingletonDefinition::Comprehension,
+            ordered_pair: OrderedPairDefinition::Kuratowski,
+            big_intersection: BigIntersectionDefinition::EmptySet,
+        },
+    ),
+    (
+        "Pairing",
+        Profile {
+            singleton: SingletonDefinition::Pairing,
+            ordered_pair: OrderedPairDefinition::Kuratowski,
+            big_intersection: BigIntersectionDefinition::EmptySet,
+        },
+    ),
+    (
+        "Wiener",
+        Profile {
+            singleton: SingletonDefinition::Direct,
+            ordered_pair: OrderedPairDefinition::Wiener,
+            big_intersection: BigIntersectionDefinition::EmptySet,
+        },
+    ),
+];
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct SetConfig {
     pub variables: bool,
+    pub bounded_quantifiers: bool,
+    pub unique_existence: bool,
     pub empty_set: bool,
     pub omega: bool,
     pub negated_relations: bool,
     pub subset: bool,
     pub singleton: bool,
     pub comprehension: bool,
+    pub replacement: bool,
+    pub class_comprehension: bool,
+    pub description: bool,
     pub power_set: bool,
     pub big_intersection: bool,
     pub big_union: bool,
     pub intersection: bool,
     pub difference: bool,
+    pub symmetric_difference: bool,
     pub union: bool,
     pub pair_set: bool,
+    pub enumeration: bool,
+    pub enumeration_as_pairs: bool,
+    pub ordered_pair: bool,
+    pub equality: bool,
+    pub equality_leibniz: bool,
+    pub definitions: BTreeMap<String, bool>,
+    pub strategy: Strategy,
+    pub profile: Profile,
+    pub relativization: Option<String>,
+    pub limits: Limits,
 }
 
+/// Parses and transforms `input`, returning the result or the error message as a string.
 pub fn run(input: &str, config: SetConfig) -> String {
-    match lexer::tokanize(input.into()) {
+    run_with_warnings(input, config).0
+}
+
+/// Like [`run`], but also returns the warnings issued while transforming.
+pub fn run_with_warnings(input: &str, config: SetConfig) -> (String, Vec<String>) {
+    let result = parse(input)
+        .map_err(Error::from)
+        .and_then(|formula| formula.transform_with_warnings(&config));
+    match result {
+        Ok((output, warnings)) => (output.to_string(), warnings),
+        Err(Error::LimitExceeded(e)) => (e.to_string(), take_warnings()),
+        Err(e) => (e.to_string(), vec![]),
+    }
+}
+
+/// Transforms `input` and returns the Lévy class of the result, or the error message.
+pub fn classify(input: &str, config: SetConfig) -> String {
+    match parse(input)
+        .map_err(Error::from)
+        .and_then(|formula| formula.transform(&config))
+    {
         Err(e) => e.to_string(),
-        Ok(tokens) => match parser::parse(tokens) {
-            Err(e) => e.to_string(),
-            Ok(syntax_tree) => syntax_tree.transform(config).to_string(),
-        },
+        Ok(output) => output.classify().to_string(),
+    }
+}
+
+/// Transforms `input` and compares the metrics of input and result.
+pub fn metrics(input: &str, config: SetConfig) -> String {
+    let formula = match parse(input) {
+        Err(e) => return e.to_string(),
+        Ok(formula) => formula,
+    };
+    let before = formula.metrics();
+    let after = match formula.transform(&config) {
+        Ok(output) => output.metrics(),
+        Err(e) => return format!("Input: {before}\n{e}"),
+    };
+    format!(
+        "Input: {before}\nOutput: {after}\nGrowth: ×{:.1}",
+        before.growth(&after)
+    )
+}
+
+/// The names of the symbols `input` defines with `def`, in order of definition.
+pub fn defined_symbols(input: &str) -> Vec<String> {
+    let Ok(tokens) = lexer::tokanize(input.into()) else {
+        return vec![];
+    };
+    let mut result = Vec::<String>::new();
+    for pair in tokens.windows(2) {
+        if let [lexer::Token::Def(..), lexer::Token::Symbol(name)] = pair {
+            if !result.contains(name) {
+                result.push(name.clone());
+            }
+        }
     }
+    result
 }
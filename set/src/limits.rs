@@ -0,0 +1,228 @@
+use crate::{metrics::Metrics, parser::SyntaxNode};
+use std::{cell::RefCell, fmt};
+
+thread_local! {static BUDGET: RefCell<Budget> = const { RefCell::new(Budget::new(Limits::DEFAULT, 0)) }}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct Limits {
+    pub max_nodes: usize,
+    pub max_depth: usize,
+    pub max_steps: usize,
+}
+
+impl Limits {
+    const DEFAULT: Limits = Limits {
+        max_nodes: 100_000,
+        max_depth: 1_000,
+        max_steps: 100_000,
+    };
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits::DEFAULT
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Nodes,
+    Depth,
+    Steps,
+}
+
+/// Returned when a transformation is aborted. `partial` is the formula after the last rewrite
+/// step performed, `metrics` describes it and `steps` counts the steps.
+#[derive(Debug)]
+pub struct LimitExceeded {
+    pub limit: Limit,
+    pub partial: SyntaxNode,
+    pub metrics: Metrics,
+    pub steps: usize,
+}
+
+impl fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let limit = match self.limit {
+            Limit::Nodes => "maximum number of nodes",
+            Limit::Depth => "maximum depth",
+            Limit::Steps => "maximum number of rewrite steps",
+        };
+        write!(
+            f,
+            "Result too large: {limit} exceeded after {} rewrite steps\nPartial result ({}): {}",
+            self.steps, self.metrics, self.partial
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+struct Budget {
+    limits: Limits,
+    nodes: usize,
+    depth: usize,
+    steps: usize,
+    rewriting: bool,
+    exceeded: Option<Limit>,
+}
+
+impl Budget {
+    const fn new(limits: Limits, nodes: usize) -> Self {
+        Budget {
+            limits,
+            nodes,
+            depth: 0,
+            steps: 0,
+            rewriting: false,
+            exceeded: None,
+        }
+    }
+}
+
+/// Starts counting for a new transformation of `formula`.
+pub fn reset(limits: Limits, formula: &SyntaxNode) {
+    let mut budget = Budget::new(limits, formula.nodes());
+    if budget.nodes > limits.max_nodes {
+        budget.exceeded = Some(Limit::Nodes);
+    } else if formula.depth() > limits.max_depth {
+        budget.exceeded = Some(Limit::Depth);
+    }
+    BUDGET.with(|rc| *rc.borrow_mut() = budget);
+}
+
+/// Replaces `node` by `rewrite(node)` as one rewrite step, unless a limit has been exceeded
+/// before or the step would exceed the maximum number of steps, in which case `node` is
+/// returned as it is. Rewrites made by `rewrite` itself are part of this step.
+pub fn rewrite(node: SyntaxNode, rewrite: impl FnOnce(SyntaxNode) -> SyntaxNode) -> SyntaxNode {
+    if BUDGET.with(|rc| rc.borrow().rewriting) {
+        return rewrite(node);
+    }
+    if !step() {
+        return node;
+    }
+    let before = node.nodes();
+    BUDGET.with(|rc| rc.borrow_mut().rewriting = true);
+    let result = rewrite(node);
+    BUDGET.with(|rc| rc.borrow_mut().rewriting = false);
+    replace(before, result.nodes(), result.depth());
+    result
+}
+
+fn step() -> bool {
+    BUDGET.with(|rc| {
+        let mut budget = rc.borrow_mut();
+        if budget.exceeded.is_some() {
+            return false;
+        }
+        if budget.steps == budget.limits.max_steps {
+            budget.exceeded = Some(Limit::Steps);
+            return false;
+        }
+        budget.steps += 1;
+        true
+    })
+}
+
+/// Accounts for a subformula of `before` nodes being replaced by one of `after` nodes and
+/// depth `depth`. If that exceeds a limit, the transformation stops after the current step.
+pub fn replace(before: usize, after: usize, depth: usize) {
+    BUDGET.with(|rc| {
+        let mut budget = rc.borrow_mut();
+        budget.nodes = (budget.nodes + after).saturating_sub(before);
+        if budget.nodes > budget.limits.max_nodes {
+            budget.exceeded.get_or_insert(Limit::Nodes);
+        }
+        if budget.depth + depth > budget.limits.max_depth {
+            budget.exceeded.get_or_insert(Limit::Depth);
+        }
+    })
+}
+
+/// Called before descending into the children of a node, so that rewrites below it know how
+/// deep in the formula they are.
+pub fn enter() {
+    BUDGET.with(|rc| rc.borrow_mut().depth += 1);
+}
+
+/// Undoes [`enter`] after the children of a node have been visited.
+pub fn leave() {
+    BUDGET.with(|rc| rc.borrow_mut().depth -= 1);
+}
+
+/// Whether a limit has been exceeded in the current transformation.
+pub fn exceeded() -> bool {
+    BUDGET.with(|rc| rc.borrow().exceeded.is_some())
+}
+
+/// Returns `result`, or the error for the limit that stopped the transformation, with `result`
+/// as partial result.
+pub fn check(result: SyntaxNode) -> Result<SyntaxNode, LimitExceeded> {
+    BUDGET.with(|rc| {
+        let budget = rc.borrow();
+        let Some(limit) = budget.exceeded else {return Ok(result)};
+        Err(LimitExceeded {
+            limit,
+            metrics: result.metrics(),
+            partial: result,
+            steps: budget.steps,
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Limit, Limits};
+    use crate::{parse, Error, SetConfig};
+
+    const INPUT: &str = "(x ∈ Pot(a) ∧ y ∈ Pot(b))";
+
+    fn transform(limits: Limits) -> Result<String, super::LimitExceeded> {
+        let config = SetConfig::builder().variables(false).limits(limits).build();
+        match parse(INPUT).unwrap().transform(&config) {
+            Ok(output) => Ok(output.to_string()),
+            Err(Error::LimitExceeded(e)) => Err(e),
+            Err(e) => panic!("{e}"),
+        }
+    }
+
+    #[test]
+    fn partial_result_is_last_completed_step() {
+        let limits = Limits {
+            max_steps: 1,
+            ..Limits::default()
+        };
+        let e = transform(limits).unwrap_err();
+        assert_eq!((e.limit, e.steps), (Limit::Steps, 1));
+        let partial = "(∃v₀ (Pot(a) = v₀ ∧ x ∈ v₀) ∧ y ∈ Pot(b))";
+        assert_eq!(e.partial.to_string(), partial);
+        assert!(e.to_string().ends_with(partial));
+    }
+
+    #[test]
+    fn depth_is_depth_of_formula() {
+        let limits = Limits {
+            max_depth: 5,
+            ..Limits::default()
+        };
+        let e = transform(limits).unwrap_err();
+        assert_eq!((e.limit, e.steps), (Limit::Depth, 1));
+        assert_eq!(e.partial.depth(), 6);
+        let limits = Limits {
+            max_depth: 3,
+            ..Limits::default()
+        };
+        let e = transform(limits).unwrap_err();
+        assert_eq!((e.steps, e.partial.to_string()), (0, INPUT.to_string()));
+        let limits = Limits {
+            max_depth: 10,
+            ..Limits::default()
+        };
+        assert!(transform(limits).is_ok());
+    }
+}
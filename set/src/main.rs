@@ -0,0 +1,207 @@
+use set::{Error, Formula, SetConfig, Strategy, TreeOptions, PRESETS};
+use std::io::BufRead;
+
+const USAGE: &str = "\
+usage: set [options] [formula...]
+
+Transforms each formula, or each line of the standard input if none is given.
+
+options:
+  --preset <name>       start from one of the presets
+  --config <file>       load the configuration from a TOML file (needs the serde feature)
+  --relativize <set>    relativize the formula to a set
+  --innermost           rewrite innermost subterms first
+  --step                apply a single rewrite step
+  --max-nodes <n>       stop when the formula grows beyond n nodes
+  --max-depth <n>       stop when the formula gets deeper than n
+  --max-steps <n>       stop after n rewrite steps
+  --tree, --ascii       draw the syntax tree of the result
+  --dot                 print the syntax tree in the Graphviz format
+  --bindings            show which quantifier binds each variable in the tree
+  --json, --sexpr       print the result as JSON or as an s-expression (needs the serde feature)
+  --classify            print the Lévy class of the result
+  --metrics             compare the size of the input and the result
+  --help                print this message
+";
+
+fn main() {
+    let mut config = SetConfig::default();
+    let mut metrics = false;
+    let mut classify = false;
+    let mut format = None;
+    let mut bindings = false;
+    let mut inputs = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--help" => {
+                print!("{USAGE}");
+                return;
+            }
+            "--metrics" => metrics = true,
+            "--classify" => classify = true,
+            "--relativize" => config.relativization = args.next(),
+            "--innermost" => config.strategy = Strategy::Innermost,
+            "--step" => config.strategy = Strategy::SingleStep,
+            "--tree" | "--ascii" | "--dot" => format = Some(arg),
+            #[cfg(feature = "serde")]
+            "--json" | "--sexpr" => format = Some(arg),
+            #[cfg(feature = "serde")]
+            "--config" => config = load(args.next()),
+            #[cfg(not(feature = "serde"))]
+            "--json" | "--sexpr" | "--config" => {
+                eprintln!("error: {arg} requires building with the serde feature");
+                std::process::exit(2);
+            }
+            "--bindings" => bindings = true,
+            "--max-nodes" => config.limits.max_nodes = limit(args.next()),
+            "--max-depth" => config.limits.max_depth = limit(args.next()),
+            "--max-steps" => config.limits.max_steps = limit(args.next()),
+            "--preset" => config = preset(args.next()),
+            _ => inputs.push(arg),
+        }
+    }
+    if inputs.is_empty() {
+        inputs = std::io::stdin()
+            .lock()
+            .lines()
+            .map_while(Result::ok)
+            .collect();
+    }
+    for input in inputs {
+        let before = match set::parse(&input) {
+            Ok(formula) => formula,
+            Err(e) => {
+                println!("{e}");
+                continue;
+            }
+        };
+        let (after, warnings) = match before.transform_with_warnings(&config) {
+            Ok((after, warnings)) => (Ok(after), warnings),
+            Err(e @ Error::LimitExceeded(_)) => (Err(e), set::take_warnings()),
+            Err(e) => (Err(e), vec![]),
+        };
+        let after = match after {
+            Ok(after) => after,
+            Err(e) => {
+                println!("{e}");
+                for warning in warnings {
+                    eprintln!("warning: {warning}");
+                }
+                if metrics {
+                    println!("Input: {}", before.metrics());
+                }
+                continue;
+            }
+        };
+        println!(
+            "{}",
+            output(&before, &after, &config, format.as_deref(), bindings)
+        );
+        for warning in warnings {
+            eprintln!("warning: {warning}");
+        }
+        if classify {
+            println!("{}", after.classify());
+        }
+        if metrics {
+            let (before, after) = (before.metrics(), after.metrics());
+            let growth = before.growth(&after);
+            println!("Input: {before}\nOutput: {after}\nGrowth: ×{growth:.1}");
+        }
+    }
+}
+
+fn output(
+    before: &Formula,
+    after: &Formula,
+    config: &SetConfig,
+    format: Option<&str>,
+    bindings: bool,
+) -> String {
+    match format {
+        #[cfg(feature = "serde")]
+        Some("--json") => after.root().to_json(),
+        #[cfg(feature = "serde")]
+        Some("--sexpr") => after.root().to_sexpr(),
+        Some(format) => render(before, after, config, format, bindings),
+        None => after.to_string(),
+    }
+}
+
+fn limit(arg: Option<String>) -> usize {
+    match arg.as_deref().map(str::parse) {
+        Some(Ok(n)) => n,
+        _ => {
+            eprintln!("error: expected a number after a limit option");
+            std::process::exit(2);
+        }
+    }
+}
+
+fn preset(arg: Option<String>) -> SetConfig {
+    let arg = arg.unwrap_or_default();
+    let preset = PRESETS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(&arg));
+    match preset {
+        Some((_, preset)) => preset(),
+        None => {
+            let names: Vec<_> = PRESETS.iter().map(|(name, _)| *name).collect();
+            eprintln!("error: expected one of the presets {}", names.join(", "));
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+fn load(arg: Option<String>) -> SetConfig {
+    let config = arg
+        .ok_or_else(|| anyhow::anyhow!("expected a file after --config"))
+        .and_then(|path| Ok(std::fs::read_to_string(path)?))
+        .and_then(|input| SetConfig::from_toml(&input));
+    match config {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("error: {e}");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Draws the syntax tree of the result. In single step mode, the rewritten subterm is
+/// highlighted.
+fn render(
+    before: &Formula,
+    after: &Formula,
+    config: &SetConfig,
+    format: &str,
+    bindings: bool,
+) -> String {
+    let options = TreeOptions {
+        ascii: format == "--ascii",
+        variables: bindings,
+        highlight: match config.strategy {
+            Strategy::SingleStep => rewritten(before, after, config),
+            _ => None,
+        },
+    };
+    match format {
+        "--dot" => after.to_dot(&options),
+        _ => after.to_ascii_tree(&options),
+    }
+}
+
+/// Path to the subterm rewritten by the single step. Renaming the variables changes every
+/// leaf, so if they were renamed the step is taken again without renaming.
+fn rewritten(before: &Formula, after: &Formula, config: &SetConfig) -> Option<Vec<usize>> {
+    if !config.variables {
+        return after.root().rewritten_from(before.root());
+    }
+    let config = SetConfig {
+        variables: false,
+        ..config.clone()
+    };
+    let after = before.transform(&config).ok()?;
+    after.root().rewritten_from(before.root())
+}
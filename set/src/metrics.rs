@@ -0,0 +1,168 @@
+use crate::parser::{Connective, NodeType, Quantifier, SyntaxNode};
+use std::fmt;
+
+#[derive(Debug)]
+pub struct Metrics {
+    pub nodes: usize,
+    pub quantifier_depth: usize,
+    pub alternations: usize,
+    pub atomic_formulas: usize,
+    pub term_depth: usize,
+}
+
+impl Metrics {
+    /// The factor by which the number of nodes grew from `self` to `output`.
+    pub fn growth(&self, output: &Metrics) -> f64 {
+        output.nodes as f64 / self.nodes as f64
+    }
+}
+
+impl fmt::Display for Metrics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} nodes, quantifier depth {}, {} alternations, {} atomic formulas, term depth {}",
+            self.nodes,
+            self.quantifier_depth,
+            self.alternations,
+            self.atomic_formulas,
+            self.term_depth
+        )
+    }
+}
+
+impl SyntaxNode {
+    /// Size and complexity measures of the formula.
+    pub fn metrics(&self) -> Metrics {
+        Metrics {
+            nodes: self.nodes(),
+            quantifier_depth: self.quantifier_depth(),
+            alternations: self.alternations(true, None),
+            atomic_formulas: self.atomic_formulas(),
+            term_depth: self.term_depth(),
+        }
+    }
+
+    /// The number of nodes of the syntax tree.
+    pub fn nodes(&self) -> usize {
+        1 + self.children.iter().map(|c| c.nodes()).sum::<usize>()
+    }
+
+    /// The number of nodes on a longest path from this node to a leaf.
+    pub fn depth(&self) -> usize {
+        1 + self.children.iter().map(|c| c.depth()).max().unwrap_or(0)
+    }
+
+    fn quantifier_depth(&self) -> usize {
+        let depth = self.children.iter().map(|c| c.quantifier_depth()).max();
+        match self.entry {
+            NodeType::Quantifier(..) | NodeType::BoundedQuantifier(..) => depth.unwrap() + 1,
+            _ => depth.unwrap_or(0),
+        }
+    }
+
+    fn alternations(&self, positive: bool, last: Option<bool>) -> usize {
+        match self.entry {
+            NodeType::Quantifier(q) | NodeType::BoundedQuantifier(q) => {
+                let universal = matches!(q, Quantifier::Universal) == positive;
+                let switch = last.map_or(0, |l| usize::from(l != universal));
+                switch
+                    + self
+                        .children
+                        .last()
+                        .unwrap()
+                        .alternations(positive, Some(universal))
+            }
+            NodeType::Connective(Connective::Negation) => {
+                self.children[0].alternations(!positive, last)
+            }
+            NodeType::Connective(Connective::Implication) => self.children[0]
+                .alternations(!positive, last)
+                .max(self.children[1].alternations(positive, last)),
+            NodeType::Connective(Connective::Biconditional) => self
+                .children
+                .iter()
+                .flat_map(|c| {
+                    [
+                        c.alternations(positive, last),
+                        c.alternations(!positive, last),
+                    ]
+                })
+                .max()
+                .unwrap(),
+            _ => self
+                .children
+                .iter()
+                .map(|c| c.alternations(positive, last))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+
+    fn atomic_formulas(&self) -> usize {
+        match self.entry {
+            NodeType::Relation(..) | NodeType::Predicate(..) => {
+                1 + self
+                    .children
+                    .iter()
+                    .map(|c| c.atomic_formulas())
+                    .sum::<usize>()
+            }
+            _ => self.children.iter().map(|c| c.atomic_formulas()).sum(),
+        }
+    }
+
+    fn term_depth(&self) -> usize {
+        let depth = self
+            .children
+            .iter()
+            .map(|c| c.term_depth())
+            .max()
+            .unwrap_or(0);
+        match self.is_set() {
+            true => depth + 1,
+            false => depth,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, SetConfig};
+
+    fn metrics(input: &str) -> [usize; 5] {
+        let m = parse(input).unwrap().metrics();
+        [
+            m.nodes,
+            m.quantifier_depth,
+            m.alternations,
+            m.atomic_formulas,
+            m.term_depth,
+        ]
+    }
+
+    #[test]
+    fn alternations_follow_the_polarity_of_quantifiers() {
+        assert_eq!(metrics("∀x ∃y ¬∀z (x ∈ y → ∃w w ∈ z)"), [16, 4, 2, 2, 1]);
+        assert_eq!(metrics("(∀x x ∈ a → ∃y y ∈ a)"), [11, 1, 0, 2, 1]);
+    }
+
+    #[test]
+    fn terms_count_towards_nodes_and_term_depth() {
+        assert_eq!(metrics("x ∈ Pot(y ∪ {z})"), [7, 0, 0, 1, 4]);
+    }
+
+    #[test]
+    fn growth_compares_input_and_output() {
+        let config = SetConfig::builder().variables(false).build();
+        let input = parse("x ∈ Pot(y)").unwrap();
+        let output = input.transform(&config).unwrap();
+        let (before, after) = (input.metrics(), output.metrics());
+        assert_eq!((before.nodes, after.nodes), (4, 21));
+        assert_eq!(before.growth(&after), 5.25);
+        assert_eq!(
+            crate::metrics("x ∈ Pot(y)", config).lines().last(),
+            Some("Growth: ×5.2")
+        );
+    }
+}
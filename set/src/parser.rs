@@ -1,5 +1,9 @@
 use crate::lexer::Token;
-use anyhow::{bail, ensure, Result};
+use anyhow::{bail, ensure, Context, Result};
+use std::{
+    ops::{Index, IndexMut},
+    sync::Arc,
+};
 
 #[derive(Debug, Clone)]
 enum ParseItem {
@@ -7,24 +11,142 @@ enum ParseItem {
     SyntaxNode(SyntaxNode),
 }
 
+/// The parser's working list of tokens and parsed subtrees. It is kept as a gap buffer so that
+/// splicing at the current position does not shift the rest of the input. The symbols defined
+/// so far travel along with it.
+#[derive(Debug, Default)]
+struct Items {
+    front: Vec<ParseItem>,
+    back: Vec<ParseItem>,
+    symbols: Vec<Symbol>,
+}
+
+impl Items {
+    fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn move_gap(&mut self, pos: usize) {
+        while self.front.len() > pos {
+            self.back.push(self.front.pop().unwrap());
+        }
+        while self.front.len() < pos {
+            self.front.push(self.back.pop().unwrap());
+        }
+    }
+
+    fn remove(&mut self, pos: usize) -> ParseItem {
+        assert!(pos < self.len());
+        self.move_gap(pos);
+        self.back.pop().unwrap()
+    }
+
+    fn insert(&mut self, pos: usize, item: ParseItem) {
+        assert!(pos <= self.len());
+        self.move_gap(pos);
+        self.front.push(item);
+    }
+}
+
+impl Index<usize> for Items {
+    type Output = ParseItem;
+
+    fn index(&self, pos: usize) -> &ParseItem {
+        match pos.checked_sub(self.front.len()) {
+            None => &self.front[pos],
+            Some(offset) => &self.back[self.back.len() - 1 - offset],
+        }
+    }
+}
+
+impl IndexMut<usize> for Items {
+    fn index_mut(&mut self, pos: usize) -> &mut ParseItem {
+        match pos.checked_sub(self.front.len()) {
+            None => &mut self.front[pos],
+            Some(offset) => {
+                let index = self.back.len() - 1 - offset;
+                &mut self.back[index]
+            }
+        }
+    }
+}
+
+impl FromIterator<ParseItem> for Items {
+    fn from_iter<I: IntoIterator<Item = ParseItem>>(iter: I) -> Self {
+        Items {
+            front: iter.into_iter().collect(),
+            back: vec![],
+            symbols: vec![],
+        }
+    }
+}
+
+impl IntoIterator for Items {
+    type Item = ParseItem;
+    type IntoIter = std::iter::Chain<
+        std::vec::IntoIter<ParseItem>,
+        std::iter::Rev<std::vec::IntoIter<ParseItem>>,
+    >;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.front.into_iter().chain(self.back.into_iter().rev())
+    }
+}
+
 #[derive(Debug, Clone)]
+struct Symbol {
+    name: Arc<str>,
+    arity: usize,
+    operation: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct SyntaxNode {
+    #[cfg_attr(feature = "serde", serde(flatten))]
     pub entry: NodeType,
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
     pub children: Vec<SyntaxNode>,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "type", content = "value", rename_all = "snake_case")
+)]
 pub enum NodeType {
     Relation(Relation),
     Connective(Connective),
     Quantifier(Quantifier),
+    BoundedQuantifier(Quantifier),
     Operator(Operator),
+    #[cfg_attr(feature = "serde", serde(with = "crate::serial::variable"))]
     Variable(u32),
     Constant(Constant),
     Comprehension,
+    Replacement,
+    ClassComprehension,
+    Description,
+    Definition(Arc<str>),
+    Operation(Arc<str>),
+    Predicate(Arc<str>),
+    Relativization,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Relation {
     Element,
     Equality,
@@ -34,7 +156,12 @@ pub enum Relation {
     NotSubset,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Connective {
     Negation,
     Conjunction,
@@ -43,13 +170,24 @@ pub enum Connective {
     Biconditional,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Quantifier {
     Universal,
     Existential,
+    UniqueExistential,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Operator {
     Singleton,
     PowerSet,
@@ -58,33 +196,89 @@ pub enum Operator {
     Union,
     Intersection,
     Difference,
+    SymmetricDifference,
     PairSet,
+    Enumeration,
+    OrderedPair,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum Constant {
     EmptySet,
     Omega,
 }
 
+/// The index of the variable called `name`, which is a letter or `v` followed by a number in
+/// ASCII or subscript digits.
+pub(crate) fn variable_index(name: &str) -> Option<u32> {
+    let mut chars = name.chars();
+    match (chars.next()?, chars.as_str()) {
+        (c, "") if c.is_alphabetic() => Some(u32::MAX - c as u32),
+        ('v', digits) if !digits.is_empty() => digits.chars().try_fold(0u32, |n, c| {
+            let digit = match c {
+                '₀'..='₉' => c as u32 - '₀' as u32,
+                _ => c.to_digit(10)?,
+            };
+            n.checked_mul(10)?.checked_add(digit)
+        }),
+        _ => None,
+    }
+}
+
 pub fn parse(tokens: Vec<Token>) -> Result<SyntaxNode> {
     tokens
         .into_iter()
         .map(ParseItem::Token)
-        .collect::<Vec<ParseItem>>()
+        .collect::<Items>()
         .parse()
 }
 
+pub fn parse_term(tokens: Vec<Token>) -> Result<SyntaxNode> {
+    let mut items = tokens
+        .into_iter()
+        .map(ParseItem::Token)
+        .collect::<Items>()
+        .parse_consts()
+        .parse_vars()?;
+    ensure!(!items.is_empty(), "Unexpected end of input");
+    items = items.parse_set_at(0)?;
+    ensure!(items.len() == 1, "Unexpected token, expected end of input");
+    match items.remove(0) {
+        ParseItem::SyntaxNode(n) if n.is_set() => Ok(n),
+        _ => bail!("Unexpected token, expected constant, variable, operation or comprehension"),
+    }
+}
+
 impl SyntaxNode {
-    fn is_set(&self) -> bool {
+    /// A node with the given entry and children, without checking that they fit.
+    pub fn new(entry: NodeType, children: Vec<SyntaxNode>) -> Self {
+        SyntaxNode { entry, children }
+    }
+
+    /// Whether the node denotes a set rather than a formula.
+    pub fn is_set(&self) -> bool {
         matches!(
             self.entry,
             NodeType::Variable(..)
                 | NodeType::Constant(..)
                 | NodeType::Operator(..)
                 | NodeType::Comprehension
+                | NodeType::Replacement
+                | NodeType::ClassComprehension
+                | NodeType::Description
+                | NodeType::Operation(..)
         )
     }
+
+    /// Whether the variable `var` occurs anywhere in `self`, free or bound.
+    pub(crate) fn mentions(&self, var: &SyntaxNode) -> bool {
+        self == var || self.children.iter().any(|c| c.mentions(var))
+    }
 }
 
 trait Parsable
@@ -93,24 +287,31 @@ where
 {
     fn parse(self) -> Result<SyntaxNode>;
     fn parse_consts(self) -> Self;
-    fn parse_vars(self) -> Self;
+    fn parse_vars(self) -> Result<Self>;
     fn parse_at(self, pos: usize) -> Result<Self>;
     fn parse_rel_at(self, pos: usize) -> Result<Self>;
     fn parse_quan_at(self, pos: usize) -> Result<Self>;
+    fn parse_desc_at(self, pos: usize) -> Result<Self>;
+    fn parse_def_at(self, pos: usize) -> Result<Self>;
+    fn parse_symbol_at(self, pos: usize) -> Result<Self>;
     fn parse_conn_at(self, pos: usize) -> Result<Self>;
+    fn parse_relativization_at(self, pos: usize) -> Result<Self>;
     fn parse_neg_at(self, pos: usize) -> Result<Self>;
     fn parse_curly_at(self, pos: usize) -> Result<Self>;
     fn parse_singleton_at(self, pos: usize) -> Result<Self>;
     fn parse_pair_at(self, pos: usize) -> Result<Self>;
+    fn parse_enum_at(self, pos: usize) -> Result<Self>;
+    fn parse_tuple_at(self, pos: usize) -> Result<Self>;
     fn parse_comp_at(self, pos: usize) -> Result<Self>;
+    fn parse_replacement_at(self, pos: usize) -> Result<Self>;
     fn parse_set_at(self, pos: usize) -> Result<Self>;
     fn parse_unop_at(self, pos: usize) -> Result<Self>;
     fn parse_binop_at(self, pos: usize) -> Result<Self>;
 }
 
-impl Parsable for Vec<ParseItem> {
+impl Parsable for Items {
     fn parse(mut self) -> Result<SyntaxNode> {
-        self = self.parse_consts().parse_vars().parse_at(0)?;
+        self = self.parse_consts().parse_vars()?.parse_at(0)?;
         ensure!(self.len() == 1, "Unexpected token, expected end of input");
         let ParseItem::SyntaxNode(result) = self.remove(0) else  {unreachable!()};
         Ok(result)
@@ -137,20 +338,17 @@ impl Parsable for Vec<ParseItem> {
             .collect()
     }
 
-    fn parse_vars(self) -> Self {
+    fn parse_vars(self) -> Result<Self> {
         self.into_iter()
             .map(|i| match i {
                 ParseItem::Token(Token::Var(v)) => {
-                    let index = if v.starts_with(&v) && v.len() > 1 {
-                        v[1..].parse().unwrap()
-                    } else {
-                        u32::MAX - v.chars().next().unwrap() as u32
-                    };
+                    let index =
+                        variable_index(&v).with_context(|| format!("Invalid variable '{v}'"))?;
                     let entry = NodeType::Variable(index);
                     let children = vec![];
-                    ParseItem::SyntaxNode(SyntaxNode { entry, children })
+                    Ok(ParseItem::SyntaxNode(SyntaxNode { entry, children }))
                 }
-                i => i,
+                i => Ok(i),
             })
             .collect()
     }
@@ -160,21 +358,25 @@ impl Parsable for Vec<ParseItem> {
         match &self[pos] {
             ParseItem::SyntaxNode(n) if n.is_set() && pos + 1 < self.len() => match self[pos + 1] {
                 ParseItem::Token(Token::Rel(..)) => self.parse_rel_at(pos),
-                ParseItem::Token(Token::BinOp(..)) => self.parse_binop_at(pos),
+                ParseItem::Token(Token::BinOp(..)) => self.parse_binop_at(pos)?.parse_at(pos),
                 _ => Ok(self),
             },
             ParseItem::SyntaxNode(..) => Ok(self),
             ParseItem::Token(Token::Quan(..)) => self.parse_quan_at(pos),
+            ParseItem::Token(Token::Desc(..)) => self.parse_desc_at(pos)?.parse_at(pos),
+            ParseItem::Token(Token::Def(..)) => self.parse_def_at(pos),
+            ParseItem::Token(Token::Symbol(..)) => self.parse_symbol_at(pos)?.parse_at(pos),
             ParseItem::Token(Token::Brack(b)) => match b.as_str() {
                 "(" => self.parse_conn_at(pos),
-                "{" => self.parse_curly_at(pos),
-                x => unimplemented!("Parser for bracket '{}' not implemented", x),
+                "{" => self.parse_curly_at(pos)?.parse_at(pos),
+                "⟨" | "\\langle" => self.parse_tuple_at(pos)?.parse_at(pos),
+                x => bail!("Unexpected token '{}'", x),
             },
             ParseItem::Token(Token::Conn(c)) => match c.as_str() {
                 "¬" | "!" | "\\lnot" => self.parse_neg_at(pos),
                 x => unimplemented!("Parser for connective '{}' not implemented", x),
             },
-            ParseItem::Token(Token::UnOp(..)) => self.parse_unop_at(pos),
+            ParseItem::Token(Token::UnOp(..)) => self.parse_unop_at(pos)?.parse_at(pos),
             ParseItem::Token(x) => bail!("Unexpected token {:?}", x),
         }
     }
@@ -183,7 +385,7 @@ impl Parsable for Vec<ParseItem> {
         assert!(matches!(&self[pos], ParseItem::SyntaxNode(n) if n.is_set()));
         ensure!(pos + 2 < self.len(), "Unexpected end of input");
         assert!(matches!(self[pos + 1], ParseItem::Token(Token::Rel(..))),);
-        self = self.parse_at(pos + 2)?;
+        self = self.parse_set_at(pos + 2)?;
         ensure!(
             matches!(&self[pos + 2], ParseItem::SyntaxNode(n) if n.is_set()),
             "Unexpected second relatum, expected constant, variable, operation or comprehension"
@@ -207,29 +409,213 @@ impl Parsable for Vec<ParseItem> {
 
     fn parse_quan_at(mut self, pos: usize) -> Result<Self> {
         assert!(matches!(self[pos], ParseItem::Token(Token::Quan(..))));
+        let mut vars = vec![];
+        loop {
+            ensure!(pos + 1 < self.len(), "Unexpected end of input");
+            ensure!(
+                matches!(&self[pos + 1], ParseItem::SyntaxNode(n) if matches!(n.entry, NodeType::Variable(..))),
+                "Unexpected Token, expected variable after quantifier"
+            );
+            let ParseItem::SyntaxNode(var) = self.remove(pos + 1) else {unreachable!()};
+            vars.push(var);
+            ensure!(pos + 1 < self.len(), "Unexpected end of input");
+            match &self[pos + 1] {
+                ParseItem::Token(Token::Brack(b)) if b == "," => {
+                    self.remove(pos + 1);
+                }
+                ParseItem::SyntaxNode(n)
+                    if matches!(n.entry, NodeType::Variable(..))
+                        && pos + 2 < self.len()
+                        && !matches!(
+                            self[pos + 2],
+                            ParseItem::Token(Token::Rel(..) | Token::BinOp(..))
+                        ) => {}
+                _ => break,
+            };
+        }
+        let bound = match &self[pos + 1] {
+            ParseItem::Token(Token::Rel(r)) if matches!(r.as_str(), "∈" | "\\in") => {
+                self.remove(pos + 1);
+                ensure!(pos + 1 < self.len(), "Unexpected end of input");
+                self = self.parse_set_at(pos + 1)?;
+                ensure!(
+                    matches!(&self[pos + 1], ParseItem::SyntaxNode(n) if n.is_set()),
+                    "Unexpected bound, expected constant, variable, operation or comprehension"
+                );
+                let ParseItem::SyntaxNode(set) = self.remove(pos + 1) else {unreachable!()};
+                Some(set)
+            }
+            _ => None,
+        };
+        // `∀x,y ∈ A` stands for `∀x ∈ A ∀y ∈ A`, where the second copy of `A` is in the scope
+        // of `x`.
+        if let Some(set) = &bound {
+            if let Some(var) = vars[..vars.len() - 1].iter().find(|v| set.mentions(v)) {
+                bail!(
+                    "The set {set} after ∈ mentions {var}, which is bound by the same quantifier"
+                );
+            }
+        }
+        ensure!(pos + 1 < self.len(), "Unexpected end of input");
+        self = self.parse_at(pos + 1)?;
+        let ParseItem::SyntaxNode(mut formula) = self.remove(pos + 1) else {unreachable!()};
+        let ParseItem::Token(Token::Quan(q)) = &self[pos] else {unreachable!()};
+        let quantifier = match q.as_str() {
+            "∀" | "\\forall" => Quantifier::Universal,
+            "∃" | "\\exists" => Quantifier::Existential,
+            "∃!" | "\\exists!" => Quantifier::UniqueExistential,
+            x => unimplemented!("Quantifier token '{}' not implemented in parser", x),
+        };
+        while let Some(var) = vars.pop() {
+            formula = match &bound {
+                Some(set) => SyntaxNode {
+                    entry: NodeType::BoundedQuantifier(quantifier),
+                    children: vec![var, set.clone(), formula],
+                },
+                None => SyntaxNode {
+                    entry: NodeType::Quantifier(quantifier),
+                    children: vec![var, formula],
+                },
+            };
+        }
+        self[pos] = ParseItem::SyntaxNode(formula);
+        Ok(self)
+    }
+
+    fn parse_desc_at(mut self, pos: usize) -> Result<Self> {
+        assert!(matches!(self[pos], ParseItem::Token(Token::Desc(..))));
         ensure!(pos + 2 < self.len(), "Unexpected end of input");
+        ensure!(
+            matches!(&self[pos + 1], ParseItem::SyntaxNode(n) if matches!(n.entry, NodeType::Variable(..))),
+            "Unexpected Token, expected variable after description operator"
+        );
         self = self.parse_at(pos + 2)?;
         let ParseItem::SyntaxNode(var) = self.remove(pos + 1) else {unreachable!()};
         let ParseItem::SyntaxNode(formula) = self.remove(pos + 1) else {unreachable!()};
         ensure!(
-            matches!(var.entry, NodeType::Variable(..)),
-            "Unexpected Token, expected variable after quantifier"
+            !formula.is_set(),
+            "Unexpected operand, expected formula after description operator"
         );
-        let ParseItem::Token(Token::Quan(q)) = &self[pos] else {unreachable!()};
-        let entry = match q.as_str() {
-            "∀" | "\\forall" => NodeType::Quantifier(Quantifier::Universal),
-            "∃" | "\\exists" => NodeType::Quantifier(Quantifier::Existential),
-            x => unimplemented!("Quantifier token '{}' not implemented in parser", x),
+        let ParseItem::Token(Token::Desc(d)) = &self[pos] else {unreachable!()};
+        let entry = match d.as_str() {
+            "ι" | "\\iota" => NodeType::Description,
+            x => unimplemented!("Description token '{}' not implemented in parser", x),
         };
         let children = vec![var, formula];
         self[pos] = ParseItem::SyntaxNode(SyntaxNode { entry, children });
         Ok(self)
     }
 
+    fn parse_def_at(mut self, pos: usize) -> Result<Self> {
+        assert!(matches!(self.remove(pos), ParseItem::Token(Token::Def(d)) if d == "def"));
+        let ParseItem::Token(Token::Symbol(name)) = self.remove(pos) else {unreachable!()};
+        ensure!(
+            pos < self.len()
+                && matches!(self.remove(pos), ParseItem::Token(Token::Brack(b)) if b == "("),
+            "Unexpected token, expected '('"
+        );
+        let mut children = vec![];
+        loop {
+            ensure!(pos < self.len(), "Unexpected end of input");
+            ensure!(
+                matches!(&self[pos], ParseItem::SyntaxNode(n) if matches!(n.entry, NodeType::Variable(..))),
+                "Unexpected token, expected variable as parameter of '{}'",
+                name
+            );
+            let ParseItem::SyntaxNode(var) = self.remove(pos) else {unreachable!()};
+            children.push(var);
+            ensure!(pos < self.len(), "Unexpected end of input");
+            match self.remove(pos) {
+                ParseItem::Token(Token::Brack(b)) if b == "," => (),
+                ParseItem::Token(Token::Brack(b)) if b == ")" => break,
+                _ => bail!("Missing token ')'"),
+            }
+        }
+        ensure!(
+            pos < self.len()
+                && matches!(self.remove(pos), ParseItem::Token(Token::Brack(b)) if b == ":="),
+            "Unexpected token, expected ':='"
+        );
+        ensure!(pos < self.len(), "Unexpected end of input");
+        self = self.parse_at(pos)?;
+        let ParseItem::SyntaxNode(body) = self.remove(pos) else {unreachable!()};
+        ensure!(
+            pos < self.len()
+                && matches!(self.remove(pos), ParseItem::Token(Token::Brack(b)) if b == ";"),
+            "Missing token ';'"
+        );
+        let name = Arc::<str>::from(name);
+        let scope = self.symbols.len();
+        self.symbols.push(Symbol {
+            name: name.clone(),
+            arity: children.len(),
+            operation: body.is_set(),
+        });
+        children.push(body);
+        ensure!(pos < self.len(), "Unexpected end of input");
+        self = self.parse_at(pos)?;
+        let ParseItem::SyntaxNode(formula) = self.remove(pos) else {unreachable!()};
+        ensure!(
+            !formula.is_set(),
+            "Unexpected operand, expected formula after definition"
+        );
+        self.symbols.truncate(scope);
+        children.push(formula);
+        let entry = NodeType::Definition(name);
+        self.insert(pos, ParseItem::SyntaxNode(SyntaxNode { entry, children }));
+        Ok(self)
+    }
+
+    fn parse_symbol_at(mut self, pos: usize) -> Result<Self> {
+        let ParseItem::Token(Token::Symbol(name)) = self.remove(pos) else {unreachable!()};
+        let Some(symbol) = self.symbols.iter().rev().find(|s| *s.name == name).cloned() else {
+            bail!("Symbol '{}' used before its definition", name)
+        };
+        ensure!(
+            pos < self.len()
+                && matches!(self.remove(pos), ParseItem::Token(Token::Brack(b)) if b == "("),
+            "Unexpected token, expected '('"
+        );
+        let mut children = vec![];
+        loop {
+            ensure!(pos < self.len(), "Unexpected end of input");
+            self = self.parse_set_at(pos)?;
+            ensure!(
+                matches!(&self[pos], ParseItem::SyntaxNode(n) if n.is_set()),
+                "Unexpected argument, expected constant, variable, operation or comprehension"
+            );
+            let ParseItem::SyntaxNode(argument) = self.remove(pos) else {unreachable!()};
+            children.push(argument);
+            ensure!(pos < self.len(), "Unexpected end of input");
+            match self.remove(pos) {
+                ParseItem::Token(Token::Brack(b)) if b == "," => (),
+                ParseItem::Token(Token::Brack(b)) if b == ")" => break,
+                _ => bail!("Missing token ')'"),
+            }
+        }
+        ensure!(
+            children.len() == symbol.arity,
+            "Symbol '{}' expects {} argument(s)",
+            name,
+            symbol.arity
+        );
+        let entry = match symbol.operation {
+            true => NodeType::Operation(symbol.name),
+            false => NodeType::Predicate(symbol.name),
+        };
+        self.insert(pos, ParseItem::SyntaxNode(SyntaxNode { entry, children }));
+        Ok(self)
+    }
+
     fn parse_conn_at(mut self, pos: usize) -> Result<Self> {
         assert!(matches!(self.remove(pos), ParseItem::Token(Token::Brack(b)) if b == "("));
         ensure!(pos < self.len(), "Unexpected end of input");
         self = self.parse_at(pos)?;
+        ensure!(pos + 1 < self.len(), "Unexpected end of input");
+        if matches!(&self[pos + 1], ParseItem::Token(Token::Brack(b)) if b == ")") {
+            self.remove(pos + 1);
+            return self.parse_relativization_at(pos);
+        }
         ensure!(pos + 2 < self.len(), "Unexpected end of input");
         self = self.parse_at(pos + 2)?;
         ensure!(pos + 3 < self.len(), "Unexpected end of input");
@@ -257,6 +643,31 @@ impl Parsable for Vec<ParseItem> {
         Ok(self)
     }
 
+    fn parse_relativization_at(mut self, pos: usize) -> Result<Self> {
+        if pos + 1 == self.len()
+            || !matches!(&self[pos + 1], ParseItem::Token(Token::Brack(b)) if b == "^")
+        {
+            return Ok(self);
+        }
+        self.remove(pos + 1);
+        ensure!(pos + 1 < self.len(), "Unexpected end of input");
+        self = self.parse_set_at(pos + 1)?;
+        ensure!(
+            matches!(&self[pos + 1], ParseItem::SyntaxNode(n) if n.is_set()),
+            "Unexpected operand, expected set after relativization operator"
+        );
+        let ParseItem::SyntaxNode(set) = self.remove(pos + 1) else {unreachable!()};
+        let ParseItem::SyntaxNode(formula) = self.remove(pos) else {unreachable!()};
+        ensure!(
+            !formula.is_set(),
+            "Unexpected operand, expected formula before relativization operator"
+        );
+        let entry = NodeType::Relativization;
+        let children = vec![formula, set];
+        self.insert(pos, ParseItem::SyntaxNode(SyntaxNode { entry, children }));
+        Ok(self)
+    }
+
     fn parse_neg_at(mut self, pos: usize) -> Result<Self> {
         assert!(
             matches!(&self[pos], ParseItem::Token(Token::Conn(c)) if matches!(c.as_str(), "¬" | "!" | "\\lnot"))
@@ -281,6 +692,18 @@ impl Parsable for Vec<ParseItem> {
         ensure!(pos + 2 < self.len(), "Unexpected end of input");
         self = self.parse_at(pos + 2)?;
         ensure!(pos + 3 < self.len(), "Unexpected end of input");
+        if matches!(&self[pos + 1], ParseItem::Token(Token::Brack(b)) if b == "|")
+            && matches!(&self[pos], ParseItem::SyntaxNode(n) if n.is_set())
+            && !(matches!(&self[pos], ParseItem::SyntaxNode(n) if matches!(n.entry, NodeType::Variable(..)))
+                && matches!(&self[pos + 3], ParseItem::Token(Token::Brack(b)) if b == "}"))
+        {
+            return self.parse_replacement_at(pos);
+        }
+        if matches!(&self[pos + 1], ParseItem::Token(Token::Brack(b)) if b == ",")
+            && matches!(&self[pos + 3], ParseItem::Token(Token::Brack(b)) if b == ",")
+        {
+            return self.parse_enum_at(pos);
+        }
         ensure!(
             matches!(self.remove(pos + 3), ParseItem::Token(Token::Brack(b)) if b == "}"),
             "Missing token '}}'"
@@ -297,7 +720,7 @@ impl Parsable for Vec<ParseItem> {
         let entry = NodeType::Operator(Operator::Singleton);
         let children = vec![child];
         self[pos] = ParseItem::SyntaxNode(SyntaxNode { entry, children });
-        self.parse_at(pos)
+        Ok(self)
     }
 
     fn parse_pair_at(mut self, pos: usize) -> Result<Self> {
@@ -311,28 +734,128 @@ impl Parsable for Vec<ParseItem> {
         let entry = NodeType::Operator(Operator::PairSet);
         let children = vec![left, right];
         self[pos] = ParseItem::SyntaxNode(SyntaxNode { entry, children });
-        self.parse_at(pos)
+        Ok(self)
+    }
+
+    fn parse_enum_at(mut self, pos: usize) -> Result<Self> {
+        let mut children = vec![];
+        loop {
+            ensure!(
+                matches!(&self[pos], ParseItem::SyntaxNode(n) if n.is_set()),
+                "Enumerated set must only contain sets"
+            );
+            let ParseItem::SyntaxNode(child) = self.remove(pos) else {unreachable!()};
+            children.push(child);
+            ensure!(pos < self.len(), "Unexpected end of input");
+            match self.remove(pos) {
+                ParseItem::Token(Token::Brack(b)) if b == "," => {
+                    ensure!(pos < self.len(), "Unexpected end of input");
+                    self = self.parse_at(pos)?;
+                }
+                ParseItem::Token(Token::Brack(b)) if b == "}" => break,
+                _ => bail!("Missing token '}}'"),
+            }
+        }
+        let entry = NodeType::Operator(Operator::Enumeration);
+        self.insert(pos, ParseItem::SyntaxNode(SyntaxNode { entry, children }));
+        Ok(self)
+    }
+
+    fn parse_tuple_at(mut self, pos: usize) -> Result<Self> {
+        assert!(
+            matches!(self.remove(pos), ParseItem::Token(Token::Brack(b)) if b == "⟨" || b == "\\langle")
+        );
+        ensure!(pos < self.len(), "Unexpected end of input");
+        self = self.parse_set_at(pos)?;
+        ensure!(pos + 2 < self.len(), "Unexpected end of input");
+        ensure!(
+            matches!(self.remove(pos + 1), ParseItem::Token(Token::Brack(b)) if b == ","),
+            "Missing token ','"
+        );
+        self = self.parse_set_at(pos + 1)?;
+        ensure!(pos + 2 < self.len(), "Unexpected end of input");
+        ensure!(
+            matches!(self.remove(pos + 2), ParseItem::Token(Token::Brack(b)) if b == "⟩" || b == "\\rangle"),
+            "Missing token '⟩'"
+        );
+        ensure!(
+            matches!(&self[pos], ParseItem::SyntaxNode(n) if n.is_set())
+                && matches!(&self[pos + 1], ParseItem::SyntaxNode(n) if n.is_set()),
+            "Ordered pair must contain two sets"
+        );
+        let ParseItem::SyntaxNode(left) = self.remove(pos) else {unreachable!()};
+        let ParseItem::SyntaxNode(right) = self.remove(pos) else {unreachable!()};
+        let entry = NodeType::Operator(Operator::OrderedPair);
+        let children = vec![left, right];
+        self.insert(pos, ParseItem::SyntaxNode(SyntaxNode { entry, children }));
+        Ok(self)
     }
 
     fn parse_comp_at(mut self, pos: usize) -> Result<Self> {
         ensure!(
-            matches!(&self[pos], ParseItem::SyntaxNode(n) if matches!(n.entry, NodeType::Relation(Relation::Element))),
-            "First part of set comprehension must be an element relation"
+            matches!(&self[pos], ParseItem::SyntaxNode(n) if matches!(n.entry, NodeType::Relation(Relation::Element) | NodeType::Variable(..))),
+            "First part of set comprehension must be an element relation or a variable"
         );
         let ParseItem::SyntaxNode(mut left) = self.remove(pos) else {unreachable!()};
         let ParseItem::SyntaxNode(right) = self.remove(pos + 1) else {unreachable!()};
-        let spec = left.children.remove(1);
-        let var = left.children.remove(0);
-        let entry = NodeType::Comprehension;
-        let children = vec![var, spec, right];
+        ensure!(
+            !right.is_set(),
+            "Second part of set comprehension must be a formula"
+        );
+        let (entry, children) = if matches!(left.entry, NodeType::Variable(..)) {
+            (NodeType::ClassComprehension, vec![left, right])
+        } else {
+            let spec = left.children.remove(1);
+            let var = left.children.remove(0);
+            (NodeType::Comprehension, vec![var, spec, right])
+        };
         self[pos] = ParseItem::SyntaxNode(SyntaxNode { entry, children });
-        self.parse_at(pos)
+        Ok(self)
     }
 
-    fn parse_set_at(self, pos: usize) -> Result<Self> {
+    fn parse_replacement_at(mut self, pos: usize) -> Result<Self> {
+        let ParseItem::SyntaxNode(term) = self.remove(pos) else {unreachable!()};
+        assert!(matches!(self.remove(pos), ParseItem::Token(Token::Brack(b)) if b == "|"));
+        let mut children = vec![term];
+        loop {
+            ensure!(
+                matches!(&self[pos], ParseItem::SyntaxNode(n)
+                    if matches!(n.entry, NodeType::Relation(Relation::Element))
+                        && matches!(n.children[0].entry, NodeType::Variable(..))),
+                "Second part of replacement must be element relations with a variable on the left"
+            );
+            let ParseItem::SyntaxNode(mut binding) = self.remove(pos) else {unreachable!()};
+            let set = binding.children.remove(1);
+            let var = binding.children.remove(0);
+            children.push(var);
+            children.push(set);
+            ensure!(pos < self.len(), "Unexpected end of input");
+            match self.remove(pos) {
+                ParseItem::Token(Token::Brack(b)) if b == "," => {
+                    ensure!(pos < self.len(), "Unexpected end of input");
+                    self = self.parse_at(pos)?;
+                }
+                ParseItem::Token(Token::Brack(b)) if b == "}" => break,
+                _ => bail!("Missing token '}}'"),
+            }
+        }
+        let entry = NodeType::Replacement;
+        self.insert(pos, ParseItem::SyntaxNode(SyntaxNode { entry, children }));
+        Ok(self)
+    }
+
+    fn parse_set_at(mut self, pos: usize) -> Result<Self> {
+        self = match &self[pos] {
+            ParseItem::Token(Token::Brack(b)) if b.as_str() == "{" => self.parse_curly_at(pos)?,
+            ParseItem::Token(Token::Brack(b)) if b.as_str() == "⟨" || b.as_str() == "\\langle" => {
+                self.parse_tuple_at(pos)?
+            }
+            ParseItem::Token(Token::UnOp(..)) => self.parse_unop_at(pos)?,
+            ParseItem::Token(Token::Desc(..)) => self.parse_desc_at(pos)?,
+            ParseItem::Token(Token::Symbol(..)) => self.parse_symbol_at(pos)?,
+            _ => self,
+        };
         match &self[pos] {
-            ParseItem::Token(Token::Brack(b)) if b.as_str() == "{" => self.parse_curly_at(pos),
-            ParseItem::Token(Token::UnOp(..)) => self.parse_unop_at(pos),
             ParseItem::SyntaxNode(n)
                 if n.is_set()
                     && pos + 1 < self.len()
@@ -375,7 +898,7 @@ impl Parsable for Vec<ParseItem> {
         };
         let children = vec![operand];
         self[pos] = ParseItem::SyntaxNode(SyntaxNode { entry, children });
-        self.parse_at(pos)
+        Ok(self)
     }
 
     fn parse_binop_at(mut self, pos: usize) -> Result<Self> {
@@ -393,11 +916,142 @@ impl Parsable for Vec<ParseItem> {
         let entry = match op.as_str() {
             "∪" | "\\cup" => NodeType::Operator(Operator::Union),
             "∩" | "\\cap" => NodeType::Operator(Operator::Intersection),
+            "△" | "\\triangle" => NodeType::Operator(Operator::SymmetricDifference),
             "\\" => NodeType::Operator(Operator::Difference),
             x => unimplemented!("Parser for binary operator '{}' not implemented", x),
         };
-        let children = vec![left, right];
+        let children = match (&entry, &right.entry) {
+            (NodeType::Operator(Operator::Union), NodeType::Operator(Operator::Union))
+            | (
+                NodeType::Operator(Operator::Intersection),
+                NodeType::Operator(Operator::Intersection),
+            ) => {
+                let mut children = right.children;
+                children.insert(0, left);
+                children
+            }
+            _ => vec![left, right],
+        };
         self[pos] = ParseItem::SyntaxNode(SyntaxNode { entry, children });
-        self.parse_at(pos)
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodeType, Operator, SyntaxNode};
+    use crate::parse;
+
+    fn root(input: &str) -> SyntaxNode {
+        parse(input).unwrap().into_root()
+    }
+
+    fn assert_round_trip(input: &str) {
+        let formula = parse(input).unwrap();
+        assert_eq!(parse(&formula.to_string()).unwrap(), formula, "{formula}");
+    }
+
+    #[test]
+    fn description_accepts_parenthesized_body() {
+        let node = root("ιx (x = a) ∈ b");
+        assert!(matches!(node.children[0].entry, NodeType::Description));
+        assert_eq!(node, root("ιx x = a ∈ b"));
+        assert_eq!(root("b ∈ ιx (x = a)").children[1], node.children[0]);
+    }
+
+    #[test]
+    fn parentheses_around_single_formula() {
+        assert_eq!(root("(x ∈ y)"), root("x ∈ y"));
+        assert_eq!(root("((x ∈ y) ∧ (y ∈ z))"), root("(x ∈ y ∧ y ∈ z)"));
+    }
+
+    #[test]
+    fn relativization() {
+        let node = root("(∀x x ∈ y)^Pot(a)");
+        assert!(matches!(node.entry, NodeType::Relativization));
+        assert_eq!(node.children[1], root("Pot(a) = b").children[0]);
+        assert_round_trip("(∀x x ∈ y)^Pot(a) ∪ b");
+        assert_round_trip("((∀x x ∈ y)^a ∧ z ∈ w)");
+        assert!(parse("(x ∈ y)^").is_err());
+        assert!(parse("^x").is_err());
+    }
+
+    #[test]
+    fn bound_set_must_not_mention_earlier_binders() {
+        assert!(parse("∀x,y ∈ x y ∈ a").is_err());
+        assert!(parse("∃x,y ∈ {x} y ∈ a").is_err());
+        assert_eq!(root("∀x,y ∈ y y ∈ a"), root("∀x ∈ y ∀y ∈ y y ∈ a"));
+        let nested = root("∀x ∈ x ∀y ∈ x y ∈ a");
+        assert_eq!(nested.to_string(), "∀x ∈ x ∀y ∈ x y ∈ a");
+        assert_round_trip("∀x ∈ x ∀y ∈ x y ∈ a");
+        assert_round_trip("∀x,y,z ∈ z (x ∈ y ∧ y ∈ z)");
+    }
+
+    #[test]
+    fn chained_operators_are_flattened_in_order() {
+        let operands = (0..100).map(|i| format!("v{i}")).collect::<Vec<_>>();
+        let node = root(&format!("x ∈ {}", operands.join(" ∪ ")));
+        let union = &node.children[1];
+        assert!(matches!(union.entry, NodeType::Operator(Operator::Union)));
+        assert_eq!(union.children.len(), 100);
+        assert!(matches!(union.children[99].entry, NodeType::Variable(99)));
+        let node = root("x ∈ a ∪ b ∪ c ∩ d ∩ e");
+        assert_eq!(node.children[1].children.len(), 3);
+        assert_eq!(node.children[1].children[2].children.len(), 3);
+        assert_round_trip("x ∈ a ∪ b ∪ c ∩ d ∩ e");
+    }
+
+    #[test]
+    fn numbered_variables() {
+        let node = root("v12 ∈ v₁₂");
+        assert!(matches!(node.children[0].entry, NodeType::Variable(12)));
+        assert_eq!(node.children[0], node.children[1]);
+        assert_eq!(node.to_string(), "v₁₂ ∈ v₁₂");
+        assert_round_trip("∀v₀ (v₀ ∈ x → v₀ ∈ v₁)");
+        assert!(parse("x ∈ v99999999999").is_err());
+    }
+
+    #[test]
+    fn symbols_survive_other_threads() {
+        let input = "def f(x) := Pot(x); def P(x) := x ∈ a; P(f(y))";
+        let formula = std::thread::spawn(move || parse(input).unwrap())
+            .join()
+            .unwrap();
+        assert_eq!(formula.to_string(), input);
+        let application = &root("def f(x) := x; f(y) ∈ y").children[2].children[0];
+        assert_eq!(application.label(), "f");
+    }
+
+    #[test]
+    fn enumerations_of_any_length() {
+        let node = root("x ∈ {a, b, c, d}");
+        assert!(matches!(
+            node.children[1].entry,
+            NodeType::Operator(Operator::Enumeration)
+        ));
+        assert_eq!(node.children[1].children.len(), 4);
+        assert!(matches!(
+            root("x ∈ {a, b}").children[1].entry,
+            NodeType::Operator(Operator::PairSet)
+        ));
+        assert_round_trip("{a, b, c} ∈ {{a}, {a, b}, ∅}");
+        assert!(parse("x ∈ {a, b,}").is_err());
+    }
+
+    #[test]
+    fn class_comprehension_needs_no_bounding_set() {
+        let node = root("x ∈ {y | ¬y ∈ y}");
+        assert!(matches!(
+            node.children[1].entry,
+            NodeType::ClassComprehension
+        ));
+        assert!(matches!(
+            root("x ∈ {y ∈ a | ¬y ∈ y}").children[1].entry,
+            NodeType::Comprehension
+        ));
+        assert_round_trip("x ∈ {y | ∃z y ∈ z}");
+        assert!(parse("x ∈ {y = a | ¬y ∈ y}").is_err());
+        assert!(parse("x ∈ {y | a}").is_err());
+        assert!(parse("x ∈ {y ∈ b | a}").is_err());
     }
 }
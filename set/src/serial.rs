@@ -0,0 +1,238 @@
+use crate::parser::{Connective, NodeType, Operator, SyntaxNode};
+use anyhow::{bail, ensure, Context, Result};
+use serde_json::{Map, Value};
+
+/// Variables are written by name, e.g. `"x"` or `"v₁"`, rather than by their index.
+pub mod variable {
+    use crate::{display::variable_name, parser::variable_index};
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    /// Writes the variable as its name, e.g. `x` or `v₁`.
+    pub fn serialize<S: Serializer>(index: &u32, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&variable_name(*index))
+    }
+
+    /// Reads a variable name as written by [`serialize`].
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        variable_index(&name).ok_or_else(|| D::Error::custom(format!("Invalid variable '{name}'")))
+    }
+}
+
+/// A node is written as `{"type": .., "value": .., "children": [..]}` in JSON and as
+/// `(type value children..)` as an S-expression, e.g. `(relation element (variable x) (variable y))`.
+/// `value` is omitted for node types without one and `children` for leaves.
+impl SyntaxNode {
+    /// Writes the syntax tree as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Reads a syntax tree written by [`SyntaxNode::to_json`].
+    pub fn from_json(json: &str) -> Result<Self> {
+        let node: SyntaxNode = serde_json::from_str(json)?;
+        node.check(&mut vec![])?;
+        Ok(node)
+    }
+
+    /// Writes the syntax tree as an S-expression.
+    pub fn to_sexpr(&self) -> String {
+        let Value::Object(entry) = serde_json::to_value(&self.entry).unwrap() else {unreachable!()};
+        let mut result = format!("({}", entry["type"].as_str().unwrap());
+        match entry.get("value") {
+            Some(Value::String(s)) => result += &format!(" {s}"),
+            Some(value) => result += &format!(" {value}"),
+            None => (),
+        }
+        for child in &self.children {
+            result += &format!(" {}", child.to_sexpr());
+        }
+        result + ")"
+    }
+
+    /// Reads a syntax tree written by [`SyntaxNode::to_sexpr`].
+    pub fn from_sexpr(input: &str) -> Result<Self> {
+        let spaced = input.replace('(', " ( ").replace(')', " ) ");
+        let mut tokens = spaced.split_whitespace().peekable();
+        let node = SyntaxNode::parse_sexpr(&mut tokens)?;
+        ensure!(
+            tokens.next().is_none(),
+            "Unexpected input after S-expression"
+        );
+        node.check(&mut vec![])?;
+        Ok(node)
+    }
+
+    fn parse_sexpr<'a>(
+        tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>,
+    ) -> Result<Self> {
+        ensure!(tokens.next() == Some("("), "Expected '('");
+        let head = tokens.next().context("Unexpected end of input")?;
+        ensure!(head != "(" && head != ")", "Expected node type after '('");
+        let mut entry = Map::new();
+        entry.insert("type".into(), head.into());
+        if let Some(value) = tokens.next_if(|t| *t != "(" && *t != ")") {
+            entry.insert("value".into(), value.into());
+        }
+        let entry = serde_json::from_value(Value::Object(entry))
+            .with_context(|| format!("Invalid node '{head}'"))?;
+        let mut children = vec![];
+        while tokens.peek() == Some(&"(") {
+            children.push(SyntaxNode::parse_sexpr(tokens)?);
+        }
+        ensure!(tokens.next() == Some(")"), "Expected ')'");
+        Ok(SyntaxNode { entry, children })
+    }
+
+    /// Rejects trees the parser cannot produce, as the transformer relies on their shape.
+    /// `definitions` are the definitions whose scope the node is in.
+    fn check<'a>(&'a self, definitions: &mut Vec<&'a SyntaxNode>) -> Result<()> {
+        let n = self.children.len();
+        let is_var = |i: usize| matches!(self.children[i].entry, NodeType::Variable(..));
+        let set = |i: usize| self.children[i].is_set();
+        let formula = |i: usize| !self.children[i].is_set();
+        let valid = match &self.entry {
+            NodeType::Variable(..) | NodeType::Constant(..) => n == 0,
+            NodeType::Connective(Connective::Negation) => n == 1 && formula(0),
+            NodeType::Connective(..) => n == 2 && formula(0) && formula(1),
+            NodeType::Relation(..) => n == 2 && set(0) && set(1),
+            NodeType::Relativization => n == 2 && formula(0) && set(1),
+            NodeType::Quantifier(..) | NodeType::ClassComprehension | NodeType::Description => {
+                n == 2 && is_var(0) && formula(1)
+            }
+            NodeType::BoundedQuantifier(..) | NodeType::Comprehension => {
+                n == 3 && is_var(0) && set(1) && formula(2)
+            }
+            NodeType::Replacement => {
+                n >= 3 && n % 2 == 1 && (1..n).step_by(2).all(is_var) && (0..n).all(set)
+            }
+            NodeType::Definition(..) => n >= 2 && (0..n - 2).all(is_var) && formula(n - 1),
+            NodeType::Operation(name) | NodeType::Predicate(name) => {
+                let Some(definition) = definitions
+                    .iter()
+                    .rev()
+                    .find(|d| matches!(&d.entry, NodeType::Definition(d) if d == name))
+                else {
+                    bail!("Symbol '{}' used outside its definition", name)
+                };
+                let arity = definition.children.len() - 2;
+                let operation = definition.children[arity].is_set();
+                let kind = match operation {
+                    true => "an operation",
+                    false => "a predicate",
+                };
+                ensure!(
+                    operation == matches!(self.entry, NodeType::Operation(..)),
+                    "Symbol '{}' is defined as {}",
+                    name,
+                    kind
+                );
+                n == arity && (0..n).all(set)
+            }
+            NodeType::Operator(op) => {
+                let arity = match op {
+                    Operator::Singleton
+                    | Operator::PowerSet
+                    | Operator::BigUnion
+                    | Operator::BigIntersection => n == 1,
+                    Operator::Difference
+                    | Operator::SymmetricDifference
+                    | Operator::PairSet
+                    | Operator::OrderedPair => n == 2,
+                    Operator::Union | Operator::Intersection | Operator::Enumeration => n >= 2,
+                };
+                arity && (0..n).all(set)
+            }
+        };
+        if !valid {
+            bail!("Unexpected children of node {:?}", self.entry);
+        }
+        if let NodeType::Definition(..) = self.entry {
+            let (formula, rest) = self.children.split_last().unwrap();
+            rest.iter().try_for_each(|c| c.check(definitions))?;
+            definitions.push(self);
+            formula.check(definitions)?;
+            definitions.pop();
+            return Ok(());
+        }
+        self.children.iter().try_for_each(|c| c.check(definitions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{parse, SyntaxNode};
+
+    const INPUTS: [&str; 3] = [
+        "∀x ∈ v1 (x ∈ v₂ ∨ {y ∈ x | y = ∅} ⊆ ω)",
+        "def f(x) := Pot(x); def P(x, y) := x ∈ f(y); P(a, b)",
+        "(∃x x ∈ a)^t",
+    ];
+
+    #[test]
+    fn variables_are_written_by_name() {
+        let node = parse("x ∈ v12").unwrap().into_root();
+        assert_eq!(
+            node.to_sexpr(),
+            "(relation element (variable x) (variable v₁₂))"
+        );
+        let json = node.to_json();
+        assert!(json.contains(r#"{"type":"variable","value":"x"}"#));
+        assert!(SyntaxNode::from_sexpr("(variable x1)").is_err());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        for input in INPUTS {
+            let node = parse(input).unwrap().into_root();
+            assert_eq!(SyntaxNode::from_json(&node.to_json()).unwrap(), node);
+        }
+    }
+
+    #[test]
+    fn sexpr_round_trip() {
+        for input in INPUTS {
+            let node = parse(input).unwrap().into_root();
+            assert_eq!(SyntaxNode::from_sexpr(&node.to_sexpr()).unwrap(), node);
+        }
+    }
+
+    #[test]
+    fn symbols_must_be_defined_with_matching_arity_and_kind() {
+        let valid = "(definition f (variable x) (operator power_set (variable x)) \
+            (relation element (operation f (variable a)) (variable b)))";
+        assert!(SyntaxNode::from_sexpr(valid).is_ok());
+        for invalid in [
+            valid.replace("(operation f (variable a))", "(operation g (variable a))"),
+            valid.replace("(operation f (variable a))", "(operation f)"),
+            valid.replace("(operation f (variable a))", "(predicate f (variable a))"),
+            "(relation element (operation f (variable a)) (variable b))".into(),
+        ] {
+            assert!(SyntaxNode::from_sexpr(&invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn children_must_be_sets_or_formulas_as_required() {
+        let valid = [
+            "(relation element (variable x) (variable y))",
+            "(connective negation (relation element (variable x) (variable y)))",
+            "(quantifier universal (variable x) (relation element (variable x) (variable y)))",
+            "(operator power_set (variable x))",
+        ];
+        let and = format!("(connective conjunction {} {})", valid[0], valid[0]);
+        for invalid in [
+            format!("(relation element {and} (variable x))"),
+            format!("(connective conjunction {} (variable x))", valid[0]),
+            "(connective negation (variable x))".into(),
+            "(quantifier universal (variable x) (variable y))".into(),
+            format!("(operator power_set {})", valid[0]),
+            format!("(relativization {} {})", valid[0], valid[1]),
+        ] {
+            assert!(SyntaxNode::from_sexpr(&invalid).is_err(), "{invalid}");
+        }
+        for input in valid {
+            assert!(SyntaxNode::from_sexpr(input).is_ok(), "{input}");
+        }
+    }
+}
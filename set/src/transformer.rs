@@ -1,26 +1,95 @@
 use std::{
     cell::RefCell,
     collections::{BTreeSet, HashMap},
+    sync::{Arc, RwLock},
 };
 
 use crate::{
+    build::{and, empty, exists, forall, forall_in, iff, implies, not, pair, pow, singleton},
+    dag::{Interner, Term},
+    limits::{self, LimitExceeded},
     parser::{Connective, Constant, NodeType, Operator, Quantifier, Relation, SyntaxNode},
-    SetConfig,
+    BigIntersectionDefinition, OrderedPairDefinition, SetConfig, SingletonDefinition, Strategy,
 };
 
 thread_local! {static USED_INDICES: RefCell<BTreeSet<u32>>  = RefCell::new(BTreeSet::new())}
+thread_local! {static WARNINGS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) }}
+// Maps the renamed variables of the input back to the names in the input, for warnings.
+thread_local! {static ORIGINAL_NAMES: RefCell<HashMap<u32, u32>> = RefCell::new(HashMap::new())}
+/// Shared by all threads, so that a rule registered once applies everywhere. Filled with the
+/// built-in rules on first use.
+static RULES: RwLock<Option<Vec<Arc<dyn Rule>>>> = RwLock::new(None);
+
+pub trait Rule: Send + Sync {
+    fn name(&self) -> &str;
+    fn pattern(&self, node: &SyntaxNode) -> bool;
+    fn condition(&self, _node: &SyntaxNode, _config: &SetConfig) -> bool {
+        true
+    }
+    fn rewrite(&self, node: SyntaxNode, config: &SetConfig) -> SyntaxNode;
+}
+
+/// Rules registered later take precedence over earlier ones and over the built-in rules. The
+/// rule applies to transformations on every thread.
+pub fn register_rule(rule: impl Rule + 'static) {
+    let mut rules = RULES.write().unwrap();
+    rules
+        .get_or_insert_with(builtin_rules)
+        .insert(0, Arc::new(rule));
+}
+
+fn with_rules<T>(f: impl FnOnce(&[Arc<dyn Rule>]) -> T) -> T {
+    if let Some(rules) = RULES.read().unwrap().as_deref() {
+        return f(rules);
+    }
+    f(RULES.write().unwrap().get_or_insert_with(builtin_rules))
+}
+
+/// The warnings issued by the last transformation.
+pub fn take_warnings() -> Vec<String> {
+    WARNINGS.with(|rc| rc.take())
+}
+
+fn warn(message: String) {
+    WARNINGS.with(|rc| {
+        if !rc.borrow().contains(&message) {
+            rc.borrow_mut().push(message);
+        }
+    });
+}
+
+/// The names of the registered rules, from highest to lowest precedence.
+pub fn rule_names() -> Vec<String> {
+    with_rules(|rules| rules.iter().map(|r| r.name().to_string()).collect())
+}
 
 impl SyntaxNode {
-    pub fn transform(self, config: SetConfig) -> Self {
-        self.variables(config)
+    /// Eliminates the notations switched on in `config`, stopping early if a limit is exceeded.
+    pub fn transform(self, config: &SetConfig) -> Result<Self, LimitExceeded> {
+        WARNINGS.with(|rc| rc.borrow_mut().clear());
+        limits::reset(config.limits, &self);
+        if config.strategy == Strategy::SingleStep {
+            let (Ok(result) | Err(result)) =
+                self.variables(config).step(config, &mut HashMap::new());
+            return limits::check(result);
+        }
+        let result = self
+            .variables(config)
+            .definitions(config, &mut HashMap::new())
+            .bounded_quantifiers(config)
+            .unique_existence(config)
             .negated_relations(config)
             .subset(config)
-            .operators(config)
+            .shared_rules(config)
             .constants(config)
+            .equality(config)
+            .relativization(config);
+        limits::check(result)
     }
 
-    fn variables(mut self, config: SetConfig) -> Self {
+    fn variables(mut self, config: &SetConfig) -> Self {
         USED_INDICES.with(|rc| rc.replace(self.collect_used_indices(BTreeSet::<u32>::new())));
+        ORIGINAL_NAMES.with(|rc| rc.borrow_mut().clear());
         if !config.variables {
             return self;
         }
@@ -36,68 +105,202 @@ impl SyntaxNode {
                 .collect::<Vec<u32>>();
             let new_indices = self.get_free_indices(relevant_indices.len());
             let mut var_map = HashMap::<u32, u32>::new();
-            for (k, v) in relevant_indices.into_iter().zip(new_indices.into_iter()) {
+            for (k, v) in relevant_indices.into_iter().zip(new_indices) {
                 var_map.insert(k, v);
             }
             self = self.replace_vars(&var_map);
             rc.replace(self.collect_used_indices(BTreeSet::<u32>::new()));
+            let original = var_map.into_iter().map(|(k, v)| (v, k)).collect();
+            ORIGINAL_NAMES.with(|names| names.replace(original));
             self
         })
     }
 
-    fn negated_relations(mut self, config: SetConfig) -> Self {
-        if !config.negated_relations {
+    fn definitions(
+        mut self,
+        config: &SetConfig,
+        defs: &mut HashMap<Arc<str>, Vec<SyntaxNode>>,
+    ) -> Self {
+        if let NodeType::Definition(name) = self.entry.clone() {
+            let formula = self.children.pop().unwrap();
+            let body = self.children.pop().unwrap().definitions(config, defs);
+            self.children.push(body);
+            let keep = config.definitions.get(&*name) == Some(&false);
+            if keep {
+                defs.remove(&name);
+            } else {
+                defs.insert(name, self.children.clone());
+            }
+            let formula = formula.definitions(config, defs);
+            // Unfolding may have stopped at a limit, leaving uses of the symbol behind.
+            if keep || limits::exceeded() {
+                self.children.push(formula);
+                return self;
+            }
+            return formula;
+        }
+        let definition = match &self.entry {
+            NodeType::Operation(name) | NodeType::Predicate(name) if !limits::exceeded() => {
+                defs.get(name).cloned()
+            }
+            _ => None,
+        };
+        if let (Strategy::Outermost, Some(definition)) = (config.strategy, &definition) {
+            return self.unfold(definition).definitions(config, defs);
+        }
+        self = self.map_children(|c| c.definitions(config, defs));
+        if let (Strategy::Innermost, Some(definition)) = (config.strategy, definition) {
+            return self.unfold(&definition);
+        }
+        self
+    }
+
+    fn bounded_quantifiers(mut self, config: &SetConfig) -> Self {
+        if !config.bounded_quantifiers {
             return self;
         }
-        for _ in 0..self.children.len() {
-            let child = self.children.remove(0).negated_relations(config);
-            self.children.push(child);
+        self = self.map_children(|c| c.bounded_quantifiers(config));
+        if matches!(self.entry, NodeType::BoundedQuantifier(..)) {
+            self = limits::rewrite(self, Self::eliminate_bounded_quantifier);
         }
-        if let NodeType::Relation(r) = self.entry {
-            let entry = match r {
-                Relation::NotEqual => NodeType::Relation(Relation::Equality),
-                Relation::NotElement => NodeType::Relation(Relation::Element),
-                Relation::NotSubset => NodeType::Relation(Relation::Subset),
-                _ => return self,
-            };
-            let children = self.children;
-            let child = SyntaxNode { entry, children };
-            self.entry = NodeType::Connective(Connective::Negation);
-            self.children = vec![child];
+        self
+    }
+
+    fn eliminate_bounded_quantifier(mut self) -> Self {
+        let NodeType::BoundedQuantifier(q) = self.entry else {unreachable!()};
+        let mut phi = self.children.remove(2);
+        let set = self.children.remove(1);
+        let mut var = self.children.remove(0);
+        let NodeType::Variable(bound) = var.entry else {unreachable!()};
+        if set.collect_used_indices(BTreeSet::<u32>::new()).contains(&bound) {
+            var = self.get_free_var();
+            phi = phi.substitute(bound, &var);
         }
+        let element = var.clone().elem(set);
+        let connective = match q {
+            Quantifier::Universal => Connective::Implication,
+            Quantifier::Existential | Quantifier::UniqueExistential => Connective::Conjunction,
+        };
+        let formula = SyntaxNode {
+            entry: NodeType::Connective(connective),
+            children: vec![element, phi],
+        };
+        self.entry = NodeType::Quantifier(q);
+        self.children = vec![var, formula];
         self
     }
 
-    fn subset(mut self, config: SetConfig) -> Self {
-        if !config.subset {
+    fn unique_existence(mut self, config: &SetConfig) -> Self {
+        if !config.unique_existence {
             return self;
         }
-        if matches!(self.entry, NodeType::Relation(Relation::Subset)) {
-            let var = self.get_free_var();
-            let antecedent = SyntaxNode {
-                entry: NodeType::Relation(Relation::Element),
-                children: vec![var.clone(), self.children.remove(0)],
-            };
-            let consequent = SyntaxNode {
-                entry: NodeType::Relation(Relation::Element),
-                children: vec![var.clone(), self.children.remove(0)],
-            };
-            let implication = SyntaxNode {
-                entry: NodeType::Connective(Connective::Implication),
-                children: vec![antecedent, consequent],
-            };
-            self.entry = NodeType::Quantifier(Quantifier::Universal);
-            self.children.push(var);
-            self.children.push(implication);
+        self = self.map_children(|c| c.unique_existence(config));
+        if matches!(
+            self.entry,
+            NodeType::Quantifier(Quantifier::UniqueExistential)
+                | NodeType::BoundedQuantifier(Quantifier::UniqueExistential)
+        ) {
+            self = limits::rewrite(self, Self::eliminate_unique_existence);
+        }
+        self
+    }
+
+    fn eliminate_unique_existence(mut self) -> Self {
+        let bounded = matches!(self.entry, NodeType::BoundedQuantifier(..));
+        let phi = self.children.pop().unwrap();
+        let var = self.children[0].clone();
+        let NodeType::Variable(bound) = var.entry else {unreachable!()};
+        let other = self.get_free_var();
+        let equality = other.clone().equals(var);
+        let implication = implies(phi.clone().substitute(bound, &other), equality);
+        let quantifier = if bounded {
+            forall_in(other, self.children[1].clone(), implication)
+        } else {
+            forall(other, implication)
+        };
+        let conjunction = and(phi, quantifier);
+        self.entry = match bounded {
+            true => NodeType::BoundedQuantifier(Quantifier::Existential),
+            false => NodeType::Quantifier(Quantifier::Existential),
+        };
+        self.children.push(conjunction);
+        self
+    }
+
+    fn negated_relations(mut self, config: &SetConfig) -> Self {
+        if !config.negated_relations {
+            return self;
+        }
+        self = self.map_children(|c| c.negated_relations(config));
+        if self.is_negated_relation() {
+            self = limits::rewrite(self, Self::eliminate_negated_relation);
+        }
+        self
+    }
+
+    fn is_negated_relation(&self) -> bool {
+        matches!(
+            self.entry,
+            NodeType::Relation(Relation::NotEqual | Relation::NotElement | Relation::NotSubset)
+        )
+    }
+
+    fn eliminate_negated_relation(mut self) -> Self {
+        let entry = match self.entry {
+            NodeType::Relation(Relation::NotEqual) => NodeType::Relation(Relation::Equality),
+            NodeType::Relation(Relation::NotElement) => NodeType::Relation(Relation::Element),
+            NodeType::Relation(Relation::NotSubset) => NodeType::Relation(Relation::Subset),
+            _ => unreachable!(),
+        };
+        let children = self.children;
+        let child = SyntaxNode { entry, children };
+        self.entry = NodeType::Connective(Connective::Negation);
+        self.children = vec![child];
+        self
+    }
+
+    fn subset(mut self, config: &SetConfig) -> Self {
+        // A single step leaves the subsets that a rewrite introduces to the following steps.
+        if !config.subset || config.strategy == Strategy::SingleStep {
+            return self;
         }
-        for _ in 0..self.children.len() {
-            let child = self.children.remove(0).subset(config);
-            self.children.push(child);
+        if matches!(self.entry, NodeType::Relation(Relation::Subset)) {
+            self = limits::rewrite(self, Self::eliminate_subset);
         }
+        self = self.map_children(|c| c.subset(config));
+        self
+    }
+
+    fn eliminate_subset(mut self) -> Self {
+        let var = self.get_free_var();
+        let antecedent = var.clone().elem(self.children.remove(0));
+        let consequent = var.clone().elem(self.children.remove(0));
+        let implication = implies(antecedent, consequent);
+        self.entry = NodeType::Quantifier(Quantifier::Universal);
+        self.children.push(var);
+        self.children.push(implication);
         self
     }
 
-    fn constants(mut self, config: SetConfig) -> Self {
+    fn constants(mut self, config: &SetConfig) -> Self {
+        if self.has_constant(config) {
+            self = limits::rewrite(self, |n| n.eliminate_constants(config));
+        }
+        self.map_children(|c| c.constants(config))
+    }
+
+    fn has_constant(&self, config: &SetConfig) -> bool {
+        matches!(
+            self.entry,
+            NodeType::Relation(Relation::Equality | Relation::Element)
+        ) && self.children.iter().any(|c| match c.entry {
+            NodeType::Constant(Constant::EmptySet) => config.empty_set,
+            NodeType::Constant(Constant::Omega) => config.omega,
+            _ => false,
+        })
+    }
+
+    fn eliminate_constants(mut self, config: &SetConfig) -> Self {
         match self.entry {
             NodeType::Relation(Relation::Equality) => {
                 match self.children[1].entry {
@@ -112,7 +315,7 @@ impl SyntaxNode {
                         self = self.phi_empty_set();
                     }
                     NodeType::Constant(Constant::Omega) if config.omega => {
-                        self = self.phi_omega().operators(config);
+                        self = self.phi_omega().rules(config);
                     }
                     _ => (),
                 }
@@ -139,150 +342,337 @@ impl SyntaxNode {
             }
             _ => (),
         }
-        for _ in 0..self.children.len() {
-            let mut child = self.children.remove(0);
-            child = child.constants(config);
-            self.children.push(child);
+        self
+    }
+
+    fn rules(mut self, config: &SetConfig) -> Self {
+        match config.strategy {
+            Strategy::Outermost => {
+                while let Some(rule) = self.find_rule(config) {
+                    self = self.apply(&rule, config);
+                }
+            }
+            Strategy::Innermost => {
+                self = self.map_children(|c| c.rules(config));
+                return match self.find_rule(config) {
+                    Some(rule) => self.apply(&rule, config).rules(config),
+                    None => self,
+                };
+            }
+            // A single step leaves the operators that a rewrite introduces to the following steps.
+            Strategy::SingleStep => return self,
         }
+        self = self.map_children(|c| c.rules(config));
         self
     }
 
-    fn operators(mut self, config: SetConfig) -> Self {
-        match self.entry {
-            NodeType::Relation(Relation::Equality) => {
-                match self.children[0].entry {
-                    NodeType::Operator(o) => match o {
-                        Operator::Singleton if config.singleton => {
-                            self = self.phi_singleton().operators(config);
-                        }
-                        Operator::PowerSet if config.power_set => {
-                            self = self.phi_power_set().subset(config);
-                        }
-                        Operator::BigIntersection if config.big_intersection => {
-                            self = self.ext();
-                        }
-                        Operator::BigUnion if config.big_union => {
-                            self = self.ext();
-                        }
-                        Operator::Intersection if config.intersection => {
-                            self = self.ext();
-                        }
-                        Operator::Difference if config.difference => {
-                            self = self.ext();
-                        }
-                        Operator::Union if config.union => {
-                            self = self.ext();
-                        }
-                        Operator::PairSet if config.pair_set => {
-                            self = self.ext();
-                        }
-                        _ => (),
-                    },
-                    NodeType::Comprehension if config.comprehension => {
-                        self = self.phi_comprehension()
-                    }
-                    _ => (),
+    fn shared_rules(self, config: &SetConfig) -> Self {
+        if config.strategy != Strategy::Outermost {
+            return self.rules(config);
+        }
+        let mut interner = Interner::new();
+        let term = interner.intern(&self);
+        // Looking up results only pays off if some subterms occur more than once.
+        if !interner.has_shared_subterms() {
+            return self.rules(config);
+        }
+        let result = self.memoised_rules(term, config, &mut interner, &mut HashMap::new());
+        interner.to_tree(result)
+    }
+
+    fn memoised_rules(
+        mut self,
+        term: Term,
+        config: &SetConfig,
+        interner: &mut Interner,
+        memo: &mut HashMap<Term, Term>,
+    ) -> Term {
+        if limits::exceeded() {
+            return term;
+        }
+        if let Some(&result) = memo.get(&term) {
+            let depth = interner.depth(result);
+            limits::replace(interner.size(term), interner.size(result), depth);
+            return result;
+        }
+        let mut current = term;
+        if let Some(rule) = self.find_rule(config) {
+            self = self.apply(&rule, config);
+            while let Some(rule) = self.find_rule(config) {
+                self = self.apply(&rule, config);
+            }
+            current = interner.intern(&self);
+        }
+        limits::enter();
+        let terms = interner.children(current).to_vec();
+        let children = self
+            .children
+            .into_iter()
+            .zip(terms)
+            .map(|(c, t)| c.memoised_rules(t, config, interner, memo))
+            .collect();
+        limits::leave();
+        let result = interner.make(self.entry, children);
+        memo.insert(term, result);
+        result
+    }
+
+    fn find_rule(&self, config: &SetConfig) -> Option<Arc<dyn Rule>> {
+        if limits::exceeded() {
+            return None;
+        }
+        with_rules(|rules| {
+            rules
+                .iter()
+                .find(|r| r.pattern(self) && r.condition(self, config))
+                .cloned()
+        })
+    }
+
+    fn apply(self, rule: &Arc<dyn Rule>, config: &SetConfig) -> Self {
+        limits::rewrite(self, |n| rule.rewrite(n, config))
+    }
+
+    /// Rewrites the leftmost-outermost subformula that an enabled elimination applies to, and
+    /// nothing else. Returns the unchanged formula as `Err` if there is no such subformula.
+    fn step(
+        mut self,
+        config: &SetConfig,
+        defs: &mut HashMap<Arc<str>, Vec<SyntaxNode>>,
+    ) -> Result<Self, Self> {
+        self = match self.step_at(config, defs) {
+            Ok(result) => return Ok(result),
+            Err(node) => node,
+        };
+        for i in 0..self.children.len() {
+            if let NodeType::Definition(name) = &self.entry {
+                if i + 1 == self.children.len() {
+                    match config.definitions.get(&**name) == Some(&false) {
+                        true => defs.remove(name),
+                        false => defs.insert(name.clone(), self.children[..i].to_vec()),
+                    };
                 }
-                match self.children[1].entry {
-                    NodeType::Operator(o) => match o {
-                        Operator::Singleton if config.singleton => {
-                            self = self.phi_singleton().operators(config);
-                        }
-                        Operator::PowerSet if config.power_set => {
-                            self.children.swap(0, 1);
-                            self = self.phi_power_set().subset(config);
-                        }
-                        Operator::BigIntersection if config.big_intersection => {
-                            self = self.ext();
-                        }
-                        Operator::BigUnion if config.big_union => {
-                            self = self.ext();
-                        }
-                        Operator::Intersection if config.intersection => {
-                            self = self.ext();
-                        }
-                        Operator::Difference if config.difference => {
-                            self = self.ext();
-                        }
-                        Operator::Union if config.union => {
-                            self = self.ext();
-                        }
-                        Operator::PairSet if config.pair_set => {
-                            self = self.ext();
-                        }
-                        _ => (),
-                    },
-                    NodeType::Comprehension if config.comprehension => {
-                        self.children.swap(0, 1);
-                        self = self.phi_comprehension();
-                    }
-                    _ => (),
+            }
+            let child = std::mem::replace(&mut self.children[i], empty());
+            limits::enter();
+            let result = child.step(config, defs);
+            limits::leave();
+            match result {
+                Ok(child) => {
+                    self.children[i] = child;
+                    return Ok(self);
                 }
+                Err(child) => self.children[i] = child,
             }
-            NodeType::Relation(Relation::Element) => {
-                match self.children[1].entry {
-                    NodeType::Operator(o) => match o {
-                        Operator::Singleton if config.singleton => {
-                            self = self.phi_singleton().operators(config);
-                        }
-                        Operator::PowerSet if config.power_set => {
-                            self = self.element_to_equality_right();
-                        }
-                        Operator::BigIntersection if config.big_intersection => {
-                            self = self.phi_big_intersection();
-                        }
-                        Operator::BigUnion if config.big_union => self = self.phi_big_union(),
-                        Operator::Intersection if config.intersection => {
-                            self = self.phi_intersection();
-                        }
-                        Operator::Difference if config.difference => self = self.phi_difference(),
-                        Operator::Union if config.union => self = self.phi_union(),
-                        Operator::PairSet if config.pair_set => self = self.phi_pair_set(),
-                        _ => (),
-                    },
-                    NodeType::Comprehension if config.comprehension => {
-                        self = self.element_to_equality_right()
-                    }
-                    _ => (),
+        }
+        Err(self)
+    }
+
+    /// Applies the first enabled elimination, in the order of the passes of
+    /// [`SyntaxNode::transform`], to `self` itself.
+    fn step_at(
+        mut self,
+        config: &SetConfig,
+        defs: &HashMap<Arc<str>, Vec<SyntaxNode>>,
+    ) -> Result<Self, Self> {
+        match &self.entry {
+            NodeType::Definition(name) => {
+                let keep = config.definitions.get(&**name) == Some(&false);
+                if !keep && !self.children.last().unwrap().uses_symbol(name) {
+                    return Ok(self.children.pop().unwrap());
                 }
-                match self.children[0].entry {
-                    NodeType::Operator(o) => match o {
-                        Operator::Singleton if config.singleton => {
-                            self = self.phi_singleton().operators(config);
-                        }
-                        Operator::PowerSet if config.power_set => {
-                            self = self.element_to_equality_left();
-                        }
-                        Operator::BigIntersection if config.big_intersection => {
-                            self = self.element_to_equality_left();
-                        }
-                        Operator::BigUnion if config.big_union => {
-                            self = self.element_to_equality_left();
-                        }
-                        Operator::Intersection if config.intersection => {
-                            self = self.element_to_equality_left();
-                        }
-                        Operator::Difference if config.difference => {
-                            self = self.element_to_equality_left();
-                        }
-                        Operator::Union if config.union => self = self.element_to_equality_left(),
-                        Operator::PairSet if config.pair_set => {
-                            self = self.element_to_equality_left();
-                        }
-                        _ => (),
-                    },
-                    NodeType::Comprehension if config.comprehension => {
-                        self = self.element_to_equality_left()
-                    }
-                    _ => (),
+            }
+            NodeType::Operation(name) | NodeType::Predicate(name) => {
+                if let Some(definition) = defs.get(name) {
+                    return Ok(self.unfold(definition));
                 }
             }
+            NodeType::Relativization => {
+                return Ok(limits::rewrite(self, Self::eliminate_relativization));
+            }
+            NodeType::BoundedQuantifier(..) if config.bounded_quantifiers => {
+                return Ok(limits::rewrite(self, Self::eliminate_bounded_quantifier));
+            }
+            NodeType::Quantifier(Quantifier::UniqueExistential)
+            | NodeType::BoundedQuantifier(Quantifier::UniqueExistential)
+                if config.unique_existence =>
+            {
+                return Ok(limits::rewrite(self, Self::eliminate_unique_existence));
+            }
+            NodeType::Relation(Relation::Subset) if config.subset => {
+                return Ok(limits::rewrite(self, Self::eliminate_subset));
+            }
+            _ if config.negated_relations && self.is_negated_relation() => {
+                return Ok(limits::rewrite(self, Self::eliminate_negated_relation));
+            }
             _ => (),
         }
-        for _ in 0..self.children.len() {
-            let child = self.children.remove(0).operators(config);
-            self.children.push(child);
+        if let Some(rule) = self.find_rule(config) {
+            return Ok(self.apply(&rule, config));
+        }
+        if self.has_constant(config) {
+            return Ok(limits::rewrite(self, |n| n.eliminate_constants(config)));
+        }
+        if config.equality && matches!(self.entry, NodeType::Relation(Relation::Equality)) {
+            return Ok(limits::rewrite(self, |n| n.eliminate_equality(config)));
+        }
+        Err(self)
+    }
+
+    fn uses_symbol(&self, name: &str) -> bool {
+        match &self.entry {
+            NodeType::Operation(symbol) | NodeType::Predicate(symbol) if **symbol == *name => true,
+            _ => self.children.iter().any(|c| c.uses_symbol(name)),
+        }
+    }
+
+    /// Eliminates only the notation at `path`, a list of child indices starting at `self`,
+    /// whether or not it is switched on in `config`. This is an application of a defined symbol,
+    /// a formula such as `a ⊆ b` or `∀x ∈ a φ`, or an operand such as `Pot(a)` of `=` or `∈`,
+    /// in which case the relation is rewritten. Returns `None` if there is no such notation.
+    pub fn unfold_at(self, path: &[usize], config: &SetConfig) -> Option<Self> {
+        USED_INDICES.with(|rc| rc.replace(self.collect_used_indices(BTreeSet::<u32>::new())));
+        ORIGINAL_NAMES.with(|rc| rc.borrow_mut().clear());
+        self.unfold_path(path, config, &mut HashMap::new())
+    }
+
+    fn unfold_path(
+        mut self,
+        path: &[usize],
+        config: &SetConfig,
+        defs: &mut HashMap<Arc<str>, Vec<SyntaxNode>>,
+    ) -> Option<Self> {
+        let Some((&i, rest)) = path.split_first() else {
+            return match &self.entry {
+                NodeType::Operation(name) | NodeType::Predicate(name) => {
+                    let definition = defs.get(name)?.clone();
+                    Some(self.unfold(&definition))
+                }
+                _ => self.eliminate_formula(),
+            };
+        };
+        let operand = rest.is_empty() && matches!(self.entry, NodeType::Relation(..));
+        if operand && !matches!(self.children.get(i)?.entry, NodeType::Operation(..)) {
+            return self.eliminate_operand(i, config);
+        }
+        if let NodeType::Definition(name) = &self.entry {
+            if i + 1 == self.children.len() {
+                defs.insert(name.clone(), self.children[..i].to_vec());
+            }
+        }
+        let child = self.children.get(i)?.clone();
+        self.children[i] = child.unfold_path(rest, config, defs)?;
+        Some(self)
+    }
+
+    /// The eliminations of [`SyntaxNode::step_at`] for formulas, without their switches.
+    fn eliminate_formula(self) -> Option<Self> {
+        let eliminate: fn(Self) -> Self = match self.entry {
+            NodeType::Relativization => Self::eliminate_relativization,
+            NodeType::BoundedQuantifier(..) => Self::eliminate_bounded_quantifier,
+            NodeType::Quantifier(Quantifier::UniqueExistential) => Self::eliminate_unique_existence,
+            NodeType::Relation(Relation::Subset) => Self::eliminate_subset,
+            _ if self.is_negated_relation() => Self::eliminate_negated_relation,
+            _ => return None,
+        };
+        Some(limits::rewrite(self, eliminate))
+    }
+
+    /// Rewrites the relation `self` with the built-in rule for its `i`-th operand.
+    fn eliminate_operand(self, i: usize, config: &SetConfig) -> Option<Self> {
+        let operand = &self.children.get(i)?.entry;
+        if *operand == NodeType::Description {
+            // `phi_description` eliminates the first description of the relation.
+            let first = self.children.iter().position(|c| c.entry == *operand);
+            return (first == Some(i)).then(|| limits::rewrite(self, Self::phi_description));
+        }
+        let builtin = builtins()
+            .into_iter()
+            .find(|b| b.side == i && b.pattern(&self))?;
+        Some(limits::rewrite(self, |n| (builtin.rewrite)(n, config)))
+    }
+
+    fn unfold(self, definition: &[SyntaxNode]) -> Self {
+        limits::rewrite(self, |n| n.instantiate(definition))
+    }
+
+    fn instantiate(self, definition: &[SyntaxNode]) -> Self {
+        let (params, body) = definition.split_at(definition.len() - 1);
+        let mut var_map = HashMap::<u32, u32>::new();
+        for (param, fresh) in params.iter().zip(self.get_free_indices(params.len())) {
+            let NodeType::Variable(var) = param.entry else {unreachable!()};
+            var_map.insert(var, fresh);
+        }
+        let mut result = body[0].clone().replace_vars(&var_map);
+        for (param, argument) in params.iter().zip(self.children) {
+            let NodeType::Variable(var) = param.entry else {unreachable!()};
+            result = result.substitute(var_map[&var], &argument);
+        }
+        result
+    }
+
+    fn equality(mut self, config: &SetConfig) -> Self {
+        if !config.equality {
+            return self;
+        }
+        self = self.map_children(|c| c.equality(config));
+        if matches!(self.entry, NodeType::Relation(Relation::Equality)) {
+            self = limits::rewrite(self, |n| n.eliminate_equality(config));
+        }
+        self
+    }
+
+    fn eliminate_equality(self, config: &SetConfig) -> Self {
+        match config.equality_leibniz {
+            true => self.leibniz(),
+            false => self.ext(),
+        }
+    }
+
+    fn relativization(mut self, config: &SetConfig) -> Self {
+        self = self.map_children(|c| c.relativization(config));
+        if !matches!(self.entry, NodeType::Relativization) {
+            return self;
+        }
+        limits::rewrite(self, Self::eliminate_relativization)
+            .rules(config)
+            .constants(config)
+            .equality(config)
+    }
+
+    fn eliminate_relativization(mut self) -> Self {
+        let set = self.children.pop().unwrap();
+        let phi = self.children.pop().unwrap();
+        phi.relativize_in(&set)
+    }
+
+    /// Restricts every quantifier to `set`, renaming bound variables that occur in `set`.
+    pub fn relativize(self, set: &SyntaxNode) -> Self {
+        let used = set.collect_used_indices(self.collect_used_indices(BTreeSet::new()));
+        USED_INDICES.with(|rc| rc.replace(used));
+        self.relativize_in(set)
+    }
+
+    fn relativize_in(mut self, set: &SyntaxNode) -> Self {
+        self = self.map_children(|c| c.relativize_in(set));
+        let connective = match self.entry {
+            NodeType::Quantifier(Quantifier::Universal)
+            | NodeType::BoundedQuantifier(Quantifier::Universal) => Connective::Implication,
+            NodeType::Quantifier(..) | NodeType::BoundedQuantifier(..) => Connective::Conjunction,
+            _ => return self,
+        };
+        let mut phi = self.children.pop().unwrap();
+        let NodeType::Variable(bound) = self.children[0].entry else {unreachable!()};
+        if set.collect_used_indices(BTreeSet::<u32>::new()).contains(&bound) {
+            let var = self.get_free_var();
+            phi = phi.substitute(bound, &var);
+            self.children[0] = var;
         }
+        let element = self.children[0].clone().elem(set.clone());
+        self.children.push(SyntaxNode {
+            entry: NodeType::Connective(connective),
+            children: vec![element, phi],
+        });
         self
     }
 
@@ -290,18 +680,22 @@ impl SyntaxNode {
         let var = self.get_free_var();
         let right = self.children.remove(1);
         let left = self.children.remove(0);
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right],
-        };
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), left],
-        };
-        let biconditional = SyntaxNode {
-            entry: NodeType::Connective(Connective::Biconditional),
-            children: vec![element_left, element_right],
-        };
+        let element_right = var.clone().elem(right);
+        let element_left = var.clone().elem(left);
+        let biconditional = iff(element_left, element_right);
+        self.entry = NodeType::Quantifier(Quantifier::Universal);
+        self.children.push(var);
+        self.children.push(biconditional);
+        self
+    }
+
+    fn leibniz(mut self) -> Self {
+        let var = self.get_free_var();
+        let right = self.children.remove(1);
+        let left = self.children.remove(0);
+        let element_left = left.elem(var.clone());
+        let element_right = right.elem(var.clone());
+        let biconditional = iff(element_left, element_right);
         self.entry = NodeType::Quantifier(Quantifier::Universal);
         self.children.push(var);
         self.children.push(biconditional);
@@ -312,18 +706,9 @@ impl SyntaxNode {
         let var = self.get_free_var();
         let right = self.children.remove(1);
         let left = self.children.remove(0);
-        let equality = SyntaxNode {
-            entry: NodeType::Relation(Relation::Equality),
-            children: vec![left, var.clone()],
-        };
-        let element = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right],
-        };
-        let conjunction = SyntaxNode {
-            entry: NodeType::Connective(Connective::Conjunction),
-            children: vec![equality, element],
-        };
+        let equality = left.equals(var.clone());
+        let element = var.clone().elem(right);
+        let conjunction = and(equality, element);
         self.entry = NodeType::Quantifier(Quantifier::Existential);
         self.children.push(var);
         self.children.push(conjunction);
@@ -334,42 +719,76 @@ impl SyntaxNode {
         let var = self.get_free_var();
         let right = self.children.remove(1);
         let left = self.children.remove(0);
-        let equality = SyntaxNode {
-            entry: NodeType::Relation(Relation::Equality),
-            children: vec![right, var.clone()],
-        };
-        let element = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left, var.clone()],
-        };
-        let conjunction = SyntaxNode {
-            entry: NodeType::Connective(Connective::Conjunction),
-            children: vec![equality, element],
-        };
+        let equality = right.equals(var.clone());
+        let element = left.elem(var.clone());
+        let conjunction = and(equality, element);
         self.entry = NodeType::Quantifier(Quantifier::Existential);
         self.children.push(var);
         self.children.push(conjunction);
         self
     }
 
+    /// Eliminates the singletons of the relation `self` as the profile says, using `direct` for
+    /// [`SingletonDefinition::Direct`].
+    fn eliminate_singleton(self, config: &SetConfig, direct: fn(Self) -> Self) -> Self {
+        match config.profile.singleton {
+            SingletonDefinition::Comprehension => self.phi_singleton(),
+            SingletonDefinition::Pairing => self.phi_singleton_pairing(),
+            SingletonDefinition::Direct => direct(self),
+        }
+    }
+
     fn phi_singleton(mut self) -> Self {
-        for _ in 0..2 {
-            let mut child = self.children.remove(0);
-            if matches!(child.entry, NodeType::Operator(Operator::Singleton)) {
+        for i in 0..self.children.len() {
+            if matches!(
+                self.children[i].entry,
+                NodeType::Operator(Operator::Singleton)
+            ) {
                 let var = self.get_free_var();
-                let grandchild = child.children.remove(0);
-                let power_set = SyntaxNode {
-                    entry: NodeType::Operator(Operator::PowerSet),
-                    children: vec![grandchild.clone()],
-                };
-                let equality = SyntaxNode {
-                    entry: NodeType::Relation(Relation::Equality),
-                    children: vec![var.clone(), grandchild],
-                };
+                let child = &mut self.children[i];
+                let grandchild = child.children.pop().unwrap();
+                let power_set = pow(grandchild.clone());
+                let equality = var.clone().equals(grandchild);
                 child.entry = NodeType::Comprehension;
                 child.children = vec![var, power_set, equality];
             }
-            self.children.push(child);
+        }
+        self
+    }
+
+    fn phi_singleton_pairing(mut self) -> Self {
+        for child in self.children.iter_mut() {
+            if matches!(child.entry, NodeType::Operator(Operator::Singleton)) {
+                child.entry = NodeType::Operator(Operator::PairSet);
+                child.children.push(child.children[0].clone());
+            }
+        }
+        self
+    }
+
+    fn phi_singleton_direct(mut self) -> Self {
+        let element = self.children[1].children.remove(0);
+        self.children[1] = element;
+        self.entry = NodeType::Relation(Relation::Equality);
+        self
+    }
+
+    fn phi_ordered_pair(mut self, definition: OrderedPairDefinition) -> Self {
+        for child in self.children.iter_mut() {
+            if !matches!(child.entry, NodeType::Operator(Operator::OrderedPair)) {
+                continue;
+            }
+            let second = child.children.pop().unwrap();
+            let first = child.children.pop().unwrap();
+            *child = match definition {
+                OrderedPairDefinition::Kuratowski => {
+                    pair(singleton(first.clone()), pair(first, second))
+                }
+                OrderedPairDefinition::Wiener => {
+                    let first = pair(singleton(first), empty());
+                    pair(first, singleton(singleton(second)))
+                }
+            };
         }
         self
     }
@@ -378,78 +797,133 @@ impl SyntaxNode {
         let var = self.get_free_var();
         let right = self.children.remove(1);
         let mut left = self.children.remove(0);
-        let element = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right],
-        };
-        let subset = SyntaxNode {
-            entry: NodeType::Relation(Relation::Subset),
-            children: vec![var.clone(), left.children.remove(0)],
-        };
-        let biconditional = SyntaxNode {
-            entry: NodeType::Connective(Connective::Biconditional),
-            children: vec![element, subset],
-        };
+        let element = var.clone().elem(right);
+        let subset = var.clone().subset_of(left.children.remove(0));
+        let biconditional = iff(element, subset);
         self.entry = NodeType::Quantifier(Quantifier::Universal);
         self.children.push(var);
         self.children.push(biconditional);
         self
     }
 
-    fn phi_big_intersection(mut self) -> Self {
-        let var = self.get_free_var();
-        let right = self.children.remove(1).children.remove(0);
-        let left = self.children.remove(0);
-        let empty_set = SyntaxNode {
-            entry: NodeType::Constant(Constant::EmptySet),
-            children: vec![],
-        };
-        let equality = SyntaxNode {
-            entry: NodeType::Relation(Relation::Equality),
-            children: vec![right.clone(), empty_set],
-        };
-        let negation = SyntaxNode {
-            entry: NodeType::Connective(Connective::Negation),
-            children: vec![equality],
+    /// `x ∈ ⋂A`. If `A` is empty, this is false unless `⋂∅` is the universe, so the undefined
+    /// convention and the convention `⋂∅ = ∅` agree here.
+    fn phi_big_intersection(mut self, convention: BigIntersectionDefinition) -> Self {
+        let set = self.children.remove(1).children.remove(0);
+        let element = self.children.remove(0);
+        let others = match convention {
+            BigIntersectionDefinition::Universe => vec![
+                BigIntersectionDefinition::Undefined,
+                BigIntersectionDefinition::EmptySet,
+            ],
+            _ => vec![BigIntersectionDefinition::Universe],
         };
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left, var.clone()],
+        SyntaxNode::warn_big_intersection(&set, &others);
+        let membership = match convention {
+            BigIntersectionDefinition::Universe => BigIntersectionDefinition::Universe,
+            _ => BigIntersectionDefinition::EmptySet,
         };
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right],
-        };
-        let implication = SyntaxNode {
-            entry: NodeType::Connective(Connective::Implication),
-            children: vec![element_left, element_right],
-        };
-        let quantifier = SyntaxNode {
-            entry: NodeType::Quantifier(Quantifier::Universal),
-            children: vec![var, implication],
-        };
-        self.entry = NodeType::Connective(Connective::Conjunction);
-        self.children.push(negation);
-        self.children.push(quantifier);
-        self
+        element.big_intersection_member(set, membership)
+    }
+
+    /// `∀v (v ∈ set → self ∈ v)`, with `set ≠ ∅` in front of it if `⋂∅ = ∅`.
+    fn big_intersection_member(
+        self,
+        set: SyntaxNode,
+        convention: BigIntersectionDefinition,
+    ) -> Self {
+        let var = self.get_free_var();
+        let implication = implies(var.clone().elem(set.clone()), self.elem(var.clone()));
+        let quantifier = forall(var, implication);
+        match convention {
+            BigIntersectionDefinition::EmptySet if !set.is_non_empty() => {
+                and(set.non_empty(), quantifier)
+            }
+            _ => quantifier,
+        }
+    }
+
+    /// `B = ⋂A` by extensionality. If `⋂A` is undefined for empty `A`, the equality states that
+    /// `A ≠ ∅` instead of `B` being the empty set or the universe, so the conventions all differ.
+    fn phi_big_intersection_equality(mut self, convention: BigIntersectionDefinition) -> Self {
+        let var = self.get_free_var();
+        let mut conjuncts = vec![];
+        let mut members = vec![];
+        for mut side in std::mem::take(&mut self.children) {
+            if !matches!(side.entry, NodeType::Operator(Operator::BigIntersection)) {
+                members.push(var.clone().elem(side));
+                continue;
+            }
+            let set = side.children.remove(0);
+            let others = [
+                BigIntersectionDefinition::Undefined,
+                BigIntersectionDefinition::EmptySet,
+                BigIntersectionDefinition::Universe,
+            ];
+            let others = others.into_iter().filter(|c| *c != convention);
+            SyntaxNode::warn_big_intersection(&set, &others.collect::<Vec<_>>());
+            let membership = match convention {
+                BigIntersectionDefinition::Undefined => {
+                    if !set.is_non_empty() {
+                        conjuncts.push(set.clone().non_empty());
+                    }
+                    BigIntersectionDefinition::Universe
+                }
+                _ => convention,
+            };
+            members.push(var.clone().big_intersection_member(set, membership));
+        }
+        let right = members.pop().unwrap();
+        let left = members.pop().unwrap();
+        conjuncts.push(forall(var, iff(left, right)));
+        SyntaxNode::connect(Connective::Conjunction, conjuncts)
+    }
+
+    /// Warns that the result would be different under the conventions `others`, unless `set`
+    /// is known to be non-empty.
+    fn warn_big_intersection(set: &SyntaxNode, others: &[BigIntersectionDefinition]) {
+        if set.is_non_empty() || others.is_empty() {
+            return;
+        }
+        let others: Vec<_> = others
+            .iter()
+            .map(|c| match c {
+                BigIntersectionDefinition::Undefined => "undefined",
+                BigIntersectionDefinition::EmptySet => "∅",
+                BigIntersectionDefinition::Universe => "the universe",
+            })
+            .collect();
+        let set = ORIGINAL_NAMES.with(|rc| set.clone().replace_vars(&rc.borrow()));
+        warn(format!(
+            "Result would differ if Durchschnitt(∅) were {} unless {set} ≠ ∅",
+            others.join(" or ")
+        ));
+    }
+
+    fn is_non_empty(&self) -> bool {
+        matches!(
+            self.entry,
+            NodeType::Operator(
+                Operator::Singleton
+                    | Operator::PairSet
+                    | Operator::Enumeration
+                    | Operator::OrderedPair
+            )
+        )
+    }
+
+    fn non_empty(self) -> Self {
+        let var = self.get_free_var();
+        exists(var.clone(), var.elem(self))
     }
 
     fn phi_big_union(mut self) -> Self {
         let var = self.get_free_var();
         let mut right = self.children.remove(1);
         let left = self.children.remove(0);
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left, var.clone()],
-        };
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right.children.remove(0)],
-        };
-        let implication = SyntaxNode {
-            entry: NodeType::Connective(Connective::Conjunction),
-            children: vec![element_left, element_right],
-        };
+        let element_right = left.elem(var.clone());
+        let element_left = var.clone().elem(right.children.remove(0));
+        let implication = and(element_left, element_right);
         self.entry = NodeType::Quantifier(Quantifier::Existential);
         self.children.push(var);
         self.children.push(implication);
@@ -457,37 +931,22 @@ impl SyntaxNode {
     }
 
     fn phi_intersection(mut self) -> Self {
-        let mut right = self.children.remove(1);
+        let right = self.children.remove(1);
         let left = self.children.remove(0);
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left.clone(), right.children.remove(1)],
-        };
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left, right.children.remove(0)],
-        };
-        self.entry = NodeType::Connective(Connective::Conjunction);
-        self.children.push(element_left);
-        self.children.push(element_right);
-        self
+        let elements = right
+            .children
+            .into_iter()
+            .map(|operand| left.clone().elem(operand))
+            .collect();
+        SyntaxNode::connect(Connective::Conjunction, elements)
     }
 
     fn phi_difference(mut self) -> Self {
         let mut right = self.children.remove(1);
         let left = self.children.remove(0);
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left.clone(), right.children.remove(1)],
-        };
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left, right.children.remove(0)],
-        };
-        let negation = SyntaxNode {
-            entry: NodeType::Connective(Connective::Negation),
-            children: vec![element_right],
-        };
+        let element_right = left.clone().elem(right.children.remove(1));
+        let element_left = left.elem(right.children.remove(0));
+        let negation = not(element_right);
         self.entry = NodeType::Connective(Connective::Conjunction);
         self.children.push(element_left);
         self.children.push(negation);
@@ -495,78 +954,168 @@ impl SyntaxNode {
     }
 
     fn phi_union(mut self) -> Self {
+        let right = self.children.remove(1);
+        let left = self.children.remove(0);
+        let elements = right
+            .children
+            .into_iter()
+            .map(|operand| left.clone().elem(operand))
+            .collect();
+        SyntaxNode::connect(Connective::Disjunction, elements)
+    }
+
+    fn phi_symmetric_difference(mut self) -> Self {
         let mut right = self.children.remove(1);
         let left = self.children.remove(0);
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left.clone(), right.children.remove(1)],
-        };
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![left, right.children.remove(0)],
+        let second = right.children.remove(1);
+        let first = right.children.remove(0);
+        let only_in = |including: SyntaxNode, excluding: SyntaxNode| {
+            let element_including = left.clone().elem(including);
+            let element_excluding = left.clone().elem(excluding);
+            let negation = not(element_excluding);
+            and(element_including, negation)
         };
+        let conjunction_left = only_in(first.clone(), second.clone());
+        let conjunction_right = only_in(second, first);
         self.entry = NodeType::Connective(Connective::Disjunction);
-        self.children.push(element_left);
-        self.children.push(element_right);
+        self.children.push(conjunction_left);
+        self.children.push(conjunction_right);
         self
     }
 
     fn phi_pair_set(mut self) -> Self {
         let mut right = self.children.remove(1);
         let left = self.children.remove(0);
-        let equality_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Equality),
-            children: vec![left.clone(), right.children.remove(1)],
-        };
-        let equality_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Equality),
-            children: vec![left, right.children.remove(0)],
-        };
+        let equality_right = left.clone().equals(right.children.remove(1));
+        let equality_left = left.equals(right.children.remove(0));
         self.entry = NodeType::Connective(Connective::Disjunction);
         self.children.push(equality_left);
         self.children.push(equality_right);
         self
     }
 
+    fn phi_enumeration(mut self) -> Self {
+        let right = self.children.remove(1);
+        let left = self.children.remove(0);
+        let equalities = right
+            .children
+            .into_iter()
+            .map(|element| left.clone().equals(element))
+            .collect();
+        SyntaxNode::connect(Connective::Disjunction, equalities)
+    }
+
+    fn phi_enumeration_as_pairs(mut self) -> Self {
+        for child in self.children.iter_mut() {
+            if matches!(child.entry, NodeType::Operator(Operator::Enumeration)) {
+                let mut operands = std::mem::take(&mut child.children).into_iter();
+                while let Some(first) = operands.next() {
+                    child.children.push(match operands.next() {
+                        None => singleton(first),
+                        Some(second) => pair(first, second),
+                    });
+                }
+                child.entry = NodeType::Operator(Operator::Union);
+            }
+        }
+        self
+    }
+
     fn phi_comprehension(mut self) -> Self {
         let right = self.children.remove(1);
         let mut left = self.children.remove(0);
-        let phi = left.children.remove(2);
+        let mut phi = left.children.remove(2);
         let spec = left.children.remove(1);
-        let var = left.children.remove(0);
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right],
-        };
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), spec],
-        };
-        let conjunction = SyntaxNode {
-            entry: NodeType::Connective(Connective::Conjunction),
-            children: vec![element_right, phi],
-        };
-        let biconditional = SyntaxNode {
-            entry: NodeType::Connective(Connective::Biconditional),
-            children: vec![element_left, conjunction],
-        };
+        let mut var = left.children.remove(0);
+        let NodeType::Variable(bound) = var.entry else {unreachable!()};
+        let used = right.collect_used_indices(spec.collect_used_indices(BTreeSet::<u32>::new()));
+        if used.contains(&bound) {
+            var = self.get_free_var();
+            phi = phi.substitute(bound, &var);
+        }
+        let element_left = var.clone().elem(right);
+        let element_right = var.clone().elem(spec);
+        let conjunction = and(element_right, phi);
+        let biconditional = iff(element_left, conjunction);
         self.entry = NodeType::Quantifier(Quantifier::Universal);
         self.children.push(var);
         self.children.push(biconditional);
         self
     }
 
+    fn phi_replacement(mut self) -> Self {
+        let mut right = self.children.remove(1);
+        let left = self.children.remove(0);
+        // Later binders shadow earlier ones, so renaming from the last binder backwards leaves
+        // every occurrence to the binder it belongs to.
+        let bindings = right.bindings();
+        let indices = self.get_free_indices(bindings.len());
+        for ((binder, scope), fresh) in bindings.into_iter().rev().zip(indices) {
+            let NodeType::Variable(var) = right.children[binder].entry else {unreachable!()};
+            let var_map = HashMap::from([(var, fresh)]);
+            right.children[binder].entry = NodeType::Variable(fresh);
+            for i in scope {
+                let child = std::mem::replace(&mut right.children[i], empty());
+                right.children[i] = child.replace_vars(&var_map);
+            }
+        }
+        // Each binder gets its own quantifier, bounded by its set: ∃x (x ∈ A ∧ ∃y (y ∈ B ∧ …)).
+        let term = right.children.remove(0);
+        self = left.equals(term);
+        while let (Some(set), Some(var)) = (right.children.pop(), right.children.pop()) {
+            self = exists(var.clone(), and(var.elem(set), self));
+        }
+        self
+    }
+
+    fn phi_class_comprehension(mut self) -> Self {
+        let mut right = self.children.remove(1);
+        let left = self.children.remove(0);
+        let phi = right.children.remove(1);
+        let NodeType::Variable(var) = right.children.remove(0).entry else {unreachable!()};
+        phi.substitute(var, &left)
+    }
+
+    fn phi_class_comprehension_equality(mut self) -> Self {
+        let var = self.get_free_var();
+        let right = self.children.remove(1);
+        let mut left = self.children.remove(0);
+        let phi = left.children.remove(1);
+        let NodeType::Variable(bound) = left.children.remove(0).entry else {unreachable!()};
+        let element = var.clone().elem(right);
+        let biconditional = iff(element, phi.substitute(bound, &var));
+        self.entry = NodeType::Quantifier(Quantifier::Universal);
+        self.children.push(var);
+        self.children.push(biconditional);
+        self
+    }
+
+    fn phi_description(mut self) -> Self {
+        let var = self.get_free_var();
+        let other = self.get_free_var();
+        let index = self
+            .children
+            .iter()
+            .position(|c| matches!(c.entry, NodeType::Description))
+            .unwrap();
+        let mut description = std::mem::replace(&mut self.children[index], var.clone());
+        let phi = description.children.remove(1);
+        let NodeType::Variable(bound) = description.children.remove(0).entry else {unreachable!()};
+        let equality = other.clone().equals(var.clone());
+        let implication = implies(phi.clone().substitute(bound, &other), equality);
+        let uniqueness = forall(other, implication);
+        let conjunction = SyntaxNode::connect(
+            Connective::Conjunction,
+            vec![phi.substitute(bound, &var), uniqueness, self],
+        );
+        exists(var, conjunction)
+    }
+
     fn phi_empty_set(mut self) -> Self {
         let var = self.get_free_var();
         let right = self.children.remove(1);
-        let element = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right],
-        };
-        let quantifier = SyntaxNode {
-            entry: NodeType::Quantifier(Quantifier::Existential),
-            children: vec![var, element],
-        };
+        let element = var.clone().elem(right);
+        let quantifier = exists(var, element);
         self.entry = NodeType::Connective(Connective::Negation);
         self.children = vec![quantifier];
         self
@@ -575,48 +1124,42 @@ impl SyntaxNode {
     fn phi_omega(mut self) -> Self {
         let right = self.children.remove(1);
         let var = self.get_free_var();
-        let empty_set = SyntaxNode {
-            entry: NodeType::Constant(Constant::EmptySet),
-            children: vec![],
-        };
-        let element_left = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![empty_set, right.clone()],
-        };
-        let element_middle = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![var.clone(), right.clone()],
-        };
-        let singleton = SyntaxNode {
-            entry: NodeType::Operator(Operator::Singleton),
-            children: vec![var.clone()],
-        };
-        let union = SyntaxNode {
-            entry: NodeType::Operator(Operator::Union),
-            children: vec![var.clone(), singleton],
-        };
-        let element_right = SyntaxNode {
-            entry: NodeType::Relation(Relation::Element),
-            children: vec![union, right],
-        };
-        let implication = SyntaxNode {
-            entry: NodeType::Connective(Connective::Implication),
-            children: vec![element_middle, element_right],
-        };
-        let quantifier = SyntaxNode {
-            entry: NodeType::Quantifier(Quantifier::Universal),
-            children: vec![var, implication],
-        };
+        let empty_set = empty();
+        let element_left = empty_set.elem(right.clone());
+        let element_middle = var.clone().elem(right.clone());
+        let singleton = singleton(var.clone());
+        let union = var.clone().union(singleton);
+        let element_right = union.elem(right);
+        let implication = implies(element_middle, element_right);
+        let quantifier = forall(var, implication);
         self.entry = NodeType::Connective(Connective::Conjunction);
         self.children = vec![element_left, quantifier];
         self
     }
 
-    fn replace_vars(mut self, map: &HashMap<u32, u32>) -> Self {
-        for _ in 0..self.children.len() {
-            let child = self.children.remove(0).replace_vars(map);
-            self.children.push(child);
+    fn connect(connective: Connective, mut operands: Vec<SyntaxNode>) -> SyntaxNode {
+        let mut result = operands.pop().unwrap();
+        while let Some(operand) = operands.pop() {
+            result = SyntaxNode {
+                entry: NodeType::Connective(connective),
+                children: vec![operand, result],
+            };
         }
+        result
+    }
+
+    fn map_children(mut self, f: impl FnMut(SyntaxNode) -> SyntaxNode) -> Self {
+        limits::enter();
+        self.children = std::mem::take(&mut self.children)
+            .into_iter()
+            .map(f)
+            .collect();
+        limits::leave();
+        self
+    }
+
+    fn replace_vars(mut self, map: &HashMap<u32, u32>) -> Self {
+        self = self.map_children(|c| c.replace_vars(map));
         if let NodeType::Variable(k) = self.entry {
             if let Some(v) = map.get(&k) {
                 self.entry = NodeType::Variable(*v);
@@ -625,7 +1168,63 @@ impl SyntaxNode {
         self
     }
 
-    fn get_free_var(&self) -> SyntaxNode {
+    pub(crate) fn substitute(self, var: u32, term: &SyntaxNode) -> Self {
+        let avoid = term.collect_used_indices(BTreeSet::<u32>::new());
+        self.substitute_avoiding(var, term, &avoid)
+    }
+
+    fn substitute_avoiding(mut self, var: u32, term: &SyntaxNode, avoid: &BTreeSet<u32>) -> Self {
+        if let NodeType::Variable(v) = self.entry {
+            return if v == var { term.clone() } else { self };
+        }
+        let mut skipped = BTreeSet::<usize>::new();
+        for (binder, scope) in self.bindings() {
+            skipped.insert(binder);
+            let NodeType::Variable(bound) = self.children[binder].entry else {unreachable!()};
+            if bound == var {
+                skipped.extend(scope);
+            } else if avoid.contains(&bound) {
+                let fresh = self.get_free_indices(1).remove(0);
+                let var_map = HashMap::from([(bound, fresh)]);
+                self.children[binder].entry = NodeType::Variable(fresh);
+                for i in scope {
+                    let child = std::mem::replace(&mut self.children[i], term.clone());
+                    self.children[i] = child.replace_vars(&var_map);
+                }
+            }
+        }
+        for (i, child) in self.children.iter_mut().enumerate() {
+            if !skipped.contains(&i) {
+                let old = std::mem::replace(child, term.clone());
+                *child = old.substitute_avoiding(var, term, avoid);
+            }
+        }
+        self
+    }
+
+    /// Pairs of the index of a bound variable and the indices of the children in its scope.
+    pub fn bindings(&self) -> Vec<(usize, Vec<usize>)> {
+        match self.entry {
+            NodeType::Quantifier(..) | NodeType::ClassComprehension | NodeType::Description => {
+                vec![(0, vec![1])]
+            }
+            NodeType::Comprehension | NodeType::BoundedQuantifier(..) => vec![(0, vec![2])],
+            NodeType::Definition(..) => {
+                let body = self.children.len() - 2;
+                (0..body).map(|param| (param, vec![body])).collect()
+            }
+            NodeType::Replacement => (1..self.children.len())
+                .step_by(2)
+                .map(|binder| {
+                    let later_sets = (binder + 3..self.children.len()).step_by(2);
+                    (binder, std::iter::once(0).chain(later_sets).collect())
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    pub(crate) fn get_free_var(&self) -> SyntaxNode {
         SyntaxNode {
             entry: NodeType::Variable(self.get_free_indices(1).remove(0)),
             children: vec![],
@@ -658,3 +1257,871 @@ impl SyntaxNode {
         set
     }
 }
+
+struct Builtin {
+    name: &'static str,
+    relation: Relation,
+    side: usize,
+    operand: NodeType,
+    enabled: fn(&SetConfig) -> bool,
+    rewrite: fn(SyntaxNode, &SetConfig) -> SyntaxNode,
+}
+
+impl Rule for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn pattern(&self, node: &SyntaxNode) -> bool {
+        node.entry == NodeType::Relation(self.relation)
+            && node.children[self.side].entry == self.operand
+    }
+
+    fn condition(&self, _node: &SyntaxNode, config: &SetConfig) -> bool {
+        (self.enabled)(config)
+    }
+
+    fn rewrite(&self, node: SyntaxNode, config: &SetConfig) -> SyntaxNode {
+        (self.rewrite)(node, config)
+    }
+}
+
+struct DescriptionRule;
+
+impl Rule for DescriptionRule {
+    fn name(&self) -> &str {
+        "R(ιx φ)"
+    }
+
+    fn pattern(&self, node: &SyntaxNode) -> bool {
+        matches!(node.entry, NodeType::Relation(..))
+            && node
+                .children
+                .iter()
+                .any(|c| matches!(c.entry, NodeType::Description))
+    }
+
+    fn condition(&self, _node: &SyntaxNode, config: &SetConfig) -> bool {
+        config.description
+    }
+
+    fn rewrite(&self, node: SyntaxNode, _config: &SetConfig) -> SyntaxNode {
+        node.phi_description()
+    }
+}
+
+fn builtin_rules() -> Vec<Arc<dyn Rule>> {
+    let mut rules: Vec<Arc<dyn Rule>> = vec![Arc::new(DescriptionRule)];
+    for builtin in builtins() {
+        rules.push(Arc::new(builtin));
+    }
+    rules
+}
+
+fn builtins() -> Vec<Builtin> {
+    vec![
+        Builtin {
+            name: "{a} = B",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::Singleton),
+            enabled: |c| c.singleton,
+            rewrite: |n, c| n.eliminate_singleton(c, SyntaxNode::ext),
+        },
+        Builtin {
+            name: "P(A) = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::PowerSet),
+            enabled: |c| c.power_set,
+            rewrite: |n, c| n.phi_power_set().subset(c),
+        },
+        Builtin {
+            name: "⋂A = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::BigIntersection),
+            enabled: |c| c.big_intersection,
+            rewrite: |n, c| n.phi_big_intersection_equality(c.profile.big_intersection),
+        },
+        Builtin {
+            name: "⋃A = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::BigUnion),
+            enabled: |c| c.big_union,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "A ∩ B = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::Intersection),
+            enabled: |c| c.intersection,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "A \\ B = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::Difference),
+            enabled: |c| c.difference,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "A △ B = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::SymmetricDifference),
+            enabled: |c| c.symmetric_difference,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "A ∪ B = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::Union),
+            enabled: |c| c.union,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "{a, b} = B",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::PairSet),
+            enabled: |c| c.pair_set,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "{a, b, c} = B",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::Enumeration),
+            enabled: |c| c.enumeration,
+            rewrite: |n, c| match c.enumeration_as_pairs {
+                true => n.phi_enumeration_as_pairs(),
+                false => n.ext(),
+            },
+        },
+        Builtin {
+            name: "{x ∈ A | φ} = B",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Comprehension,
+            enabled: |c| c.comprehension,
+            rewrite: |n, _| n.phi_comprehension(),
+        },
+        Builtin {
+            name: "{t | x ∈ A} = B",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Replacement,
+            enabled: |c| c.replacement,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "{x | φ} = B",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::ClassComprehension,
+            enabled: |c| c.class_comprehension,
+            rewrite: |n, _| n.phi_class_comprehension_equality(),
+        },
+        Builtin {
+            name: "A = {a}",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::Singleton),
+            enabled: |c| c.singleton,
+            rewrite: |n, c| n.eliminate_singleton(c, SyntaxNode::ext),
+        },
+        Builtin {
+            name: "C = P(A)",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::PowerSet),
+            enabled: |c| c.power_set,
+            rewrite: |mut n, c| {
+                n.children.swap(0, 1);
+                n.phi_power_set().subset(c)
+            },
+        },
+        Builtin {
+            name: "C = ⋂A",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::BigIntersection),
+            enabled: |c| c.big_intersection,
+            rewrite: |n, c| n.phi_big_intersection_equality(c.profile.big_intersection),
+        },
+        Builtin {
+            name: "C = ⋃A",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::BigUnion),
+            enabled: |c| c.big_union,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "C = A ∩ B",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::Intersection),
+            enabled: |c| c.intersection,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "C = A \\ B",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::Difference),
+            enabled: |c| c.difference,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "C = A △ B",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::SymmetricDifference),
+            enabled: |c| c.symmetric_difference,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "C = A ∪ B",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::Union),
+            enabled: |c| c.union,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "A = {a, b}",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::PairSet),
+            enabled: |c| c.pair_set,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "A = {a, b, c}",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::Enumeration),
+            enabled: |c| c.enumeration,
+            rewrite: |n, c| match c.enumeration_as_pairs {
+                true => n.phi_enumeration_as_pairs(),
+                false => n.ext(),
+            },
+        },
+        Builtin {
+            name: "A = {x ∈ A | φ}",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Comprehension,
+            enabled: |c| c.comprehension,
+            rewrite: |mut n, _| {
+                n.children.swap(0, 1);
+                n.phi_comprehension()
+            },
+        },
+        Builtin {
+            name: "A = {t | x ∈ A}",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Replacement,
+            enabled: |c| c.replacement,
+            rewrite: |n, _| n.ext(),
+        },
+        Builtin {
+            name: "A = {x | φ}",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::ClassComprehension,
+            enabled: |c| c.class_comprehension,
+            rewrite: |mut n, _| {
+                n.children.swap(0, 1);
+                n.phi_class_comprehension_equality()
+            },
+        },
+        Builtin {
+            name: "x ∈ {a}",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::Singleton),
+            enabled: |c| c.singleton,
+            rewrite: |n, c| n.eliminate_singleton(c, SyntaxNode::phi_singleton_direct),
+        },
+        Builtin {
+            name: "x ∈ P(A)",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::PowerSet),
+            enabled: |c| c.power_set,
+            rewrite: |n, _| n.element_to_equality_right(),
+        },
+        Builtin {
+            name: "x ∈ ⋂A",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::BigIntersection),
+            enabled: |c| c.big_intersection,
+            rewrite: |n, c| n.phi_big_intersection(c.profile.big_intersection),
+        },
+        Builtin {
+            name: "x ∈ ⋃A",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::BigUnion),
+            enabled: |c| c.big_union,
+            rewrite: |n, _| n.phi_big_union(),
+        },
+        Builtin {
+            name: "x ∈ A ∩ B",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::Intersection),
+            enabled: |c| c.intersection,
+            rewrite: |n, _| n.phi_intersection(),
+        },
+        Builtin {
+            name: "x ∈ A \\ B",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::Difference),
+            enabled: |c| c.difference,
+            rewrite: |n, _| n.phi_difference(),
+        },
+        Builtin {
+            name: "x ∈ A △ B",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::SymmetricDifference),
+            enabled: |c| c.symmetric_difference,
+            rewrite: |n, _| n.phi_symmetric_difference(),
+        },
+        Builtin {
+            name: "x ∈ A ∪ B",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::Union),
+            enabled: |c| c.union,
+            rewrite: |n, _| n.phi_union(),
+        },
+        Builtin {
+            name: "x ∈ {a, b}",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::PairSet),
+            enabled: |c| c.pair_set,
+            rewrite: |n, _| n.phi_pair_set(),
+        },
+        Builtin {
+            name: "x ∈ {a, b, c}",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::Enumeration),
+            enabled: |c| c.enumeration,
+            rewrite: |n, c| match c.enumeration_as_pairs {
+                true => n.phi_enumeration_as_pairs(),
+                false => n.phi_enumeration(),
+            },
+        },
+        Builtin {
+            name: "x ∈ {x ∈ A | φ}",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Comprehension,
+            enabled: |c| c.comprehension,
+            rewrite: |n, _| n.element_to_equality_right(),
+        },
+        Builtin {
+            name: "x ∈ {t | x ∈ A}",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Replacement,
+            enabled: |c| c.replacement,
+            rewrite: |n, _| n.phi_replacement(),
+        },
+        Builtin {
+            name: "x ∈ {x | φ}",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::ClassComprehension,
+            enabled: |c| c.class_comprehension,
+            rewrite: |n, _| match n.children[0].entry {
+                NodeType::Variable(..) => n.phi_class_comprehension(),
+                _ => n.element_to_equality_left(),
+            },
+        },
+        Builtin {
+            name: "{a} ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::Singleton),
+            enabled: |c| c.singleton,
+            rewrite: |n, c| n.eliminate_singleton(c, SyntaxNode::element_to_equality_left),
+        },
+        Builtin {
+            name: "P(A) ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::PowerSet),
+            enabled: |c| c.power_set,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "⋂A ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::BigIntersection),
+            enabled: |c| c.big_intersection,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "⋃A ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::BigUnion),
+            enabled: |c| c.big_union,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "A ∩ B ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::Intersection),
+            enabled: |c| c.intersection,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "A \\ B ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::Difference),
+            enabled: |c| c.difference,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "A △ B ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::SymmetricDifference),
+            enabled: |c| c.symmetric_difference,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "A ∪ B ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::Union),
+            enabled: |c| c.union,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "{a, b} ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::PairSet),
+            enabled: |c| c.pair_set,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "{a, b, c} ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::Enumeration),
+            enabled: |c| c.enumeration,
+            rewrite: |n, c| match c.enumeration_as_pairs {
+                true => n.phi_enumeration_as_pairs(),
+                false => n.element_to_equality_left(),
+            },
+        },
+        Builtin {
+            name: "{x ∈ A | φ} ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Comprehension,
+            enabled: |c| c.comprehension,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "{t | x ∈ A} ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Replacement,
+            enabled: |c| c.replacement,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "{x | φ} ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::ClassComprehension,
+            enabled: |c| c.class_comprehension,
+            rewrite: |n, _| n.element_to_equality_left(),
+        },
+        Builtin {
+            name: "⟨a, b⟩ = C",
+            relation: Relation::Equality,
+            side: 0,
+            operand: NodeType::Operator(Operator::OrderedPair),
+            enabled: |c| c.ordered_pair,
+            rewrite: |n, c| n.phi_ordered_pair(c.profile.ordered_pair),
+        },
+        Builtin {
+            name: "C = ⟨a, b⟩",
+            relation: Relation::Equality,
+            side: 1,
+            operand: NodeType::Operator(Operator::OrderedPair),
+            enabled: |c| c.ordered_pair,
+            rewrite: |n, c| n.phi_ordered_pair(c.profile.ordered_pair),
+        },
+        Builtin {
+            name: "x ∈ ⟨a, b⟩",
+            relation: Relation::Element,
+            side: 1,
+            operand: NodeType::Operator(Operator::OrderedPair),
+            enabled: |c| c.ordered_pair,
+            rewrite: |n, c| n.phi_ordered_pair(c.profile.ordered_pair),
+        },
+        Builtin {
+            name: "⟨a, b⟩ ∈ y",
+            relation: Relation::Element,
+            side: 0,
+            operand: NodeType::Operator(Operator::OrderedPair),
+            enabled: |c| c.ordered_pair,
+            rewrite: |n, c| n.phi_ordered_pair(c.profile.ordered_pair),
+        },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{register_rule, rule_names, Rule, USED_INDICES};
+    use crate::BigIntersectionDefinition::{self, EmptySet, Undefined, Universe};
+    use crate::{
+        build::{exists, not, var},
+        parse, NodeType, SetConfig, Strategy, SyntaxNode, PROFILES,
+    };
+    use std::collections::BTreeSet;
+
+    fn transform(input: &str, config: &SetConfig) -> String {
+        parse(input).unwrap().transform(config).unwrap().to_string()
+    }
+
+    fn replacement_only() -> SetConfig {
+        SetConfig::builder()
+            .variables(false)
+            .singleton(false)
+            .union(false)
+            .build()
+    }
+
+    #[test]
+    fn copies_of_a_subterm_are_rewritten_once() {
+        let config = SetConfig::builder().variables(false).build();
+        let single = transform("x ∈ Pot(y)", &config);
+        let input = "(x ∈ Pot(y) ∧ x ∈ Pot(y))";
+        assert_eq!(transform(input, &config), format!("({single} ∧ {single})"));
+        let config = SetConfig {
+            strategy: Strategy::Innermost,
+            ..config
+        };
+        assert_ne!(transform(input, &config), format!("({single} ∧ {single})"));
+    }
+
+    #[test]
+    fn replacement_keeps_sets_outside_their_own_binder() {
+        let config = replacement_only();
+        assert_eq!(
+            transform("z ∈ {{x} | x ∈ x}", &config),
+            "∃v₀ (v₀ ∈ x ∧ z = {v₀})"
+        );
+    }
+
+    #[test]
+    fn replacement_scopes_binders_over_later_sets() {
+        let config = replacement_only();
+        assert_eq!(
+            transform("z ∈ {x ∪ y | x ∈ y, y ∈ x}", &config),
+            "∃v₀ (v₀ ∈ y ∧ ∃v₁ (v₁ ∈ v₀ ∧ z = v₀ ∪ v₁))"
+        );
+        let output = parse("z ∈ {x ∪ y | x ∈ y, y ∈ x}").unwrap();
+        let output = output.transform(&config).unwrap();
+        assert!(output.classify().is_delta_zero());
+    }
+
+    fn step(input: &str) -> String {
+        let config = SetConfig::builder()
+            .variables(false)
+            .strategy(Strategy::SingleStep)
+            .build();
+        transform(input, &config)
+    }
+
+    #[test]
+    fn single_step_rewrites_leftmost_outermost_redex_only() {
+        assert_eq!(
+            step("(x ∈ A ∪ B ∧ y ∈ C ∩ D)"),
+            "((x ∈ A ∨ x ∈ B) ∧ y ∈ C ∩ D)"
+        );
+        assert_eq!(step("x ⊆ A ∪ B"), "∀v₀ (v₀ ∈ x → v₀ ∈ A ∪ B)");
+        assert_eq!(step("x ∉ Pot(A)"), "¬x ∈ Pot(A)");
+        assert_eq!(step("ω = A"), "(∅ ∈ A ∧ ∀v₀ (v₀ ∈ A → v₀ ∪ {v₀} ∈ A))");
+    }
+
+    #[test]
+    fn single_steps_unfold_then_drop_definitions() {
+        let first = step("def f(x) := Pot(x); f(y) ∈ y");
+        assert_eq!(first, "def f(x) := Pot(x); Pot(y) ∈ y");
+        assert_eq!(step(&first), "Pot(y) ∈ y");
+    }
+
+    fn unfold_at(input: &str, path: &[usize]) -> Option<String> {
+        let formula = parse(input).unwrap();
+        let result = formula.unfold_at(path, &SetConfig::primitive());
+        result.map(|formula| formula.to_string())
+    }
+
+    #[test]
+    fn unfold_at_eliminates_only_the_selected_notation() {
+        let input = "def f(x) := Pot(x); (f(a) ∈ f(b) ∧ c ⊆ Pot(b))";
+        assert_eq!(
+            unfold_at(input, &[2, 0, 1]).unwrap(),
+            "def f(x) := Pot(x); (f(a) ∈ Pot(b) ∧ c ⊆ Pot(b))"
+        );
+        assert_eq!(
+            unfold_at(input, &[2, 1]).unwrap(),
+            "def f(x) := Pot(x); (f(a) ∈ f(b) ∧ ∀v₀ (v₀ ∈ c → v₀ ∈ Pot(b)))"
+        );
+        assert_eq!(unfold_at(input, &[2, 1, 1]), None);
+        assert_eq!(unfold_at(input, &[2, 0]), None);
+    }
+
+    #[test]
+    fn unfold_at_rewrites_the_relation_of_an_operand() {
+        let input = "(x ∈ a ∧ Pot(a) = b ∪ c)";
+        assert_eq!(
+            unfold_at(input, &[1, 1]).unwrap(),
+            "(x ∈ a ∧ ∀v₀ (v₀ ∈ Pot(a) ↔ v₀ ∈ b ∪ c))"
+        );
+        assert_eq!(unfold_at(input, &[0, 1]), None);
+        assert_eq!(
+            unfold_at("x ∈ {y ∈ a | y = y}", &[1]).unwrap(),
+            "∃v₀ ({y ∈ a | y = y} = v₀ ∧ x ∈ v₀)"
+        );
+        assert_eq!(unfold_at("x ∈ {y ∈ a | y = y}", &[]), None);
+    }
+
+    #[test]
+    fn relativize_renames_away_from_formula_and_set() {
+        let formula = parse("∀x x ∈ v0").unwrap();
+        let set = crate::build::var("x");
+        let relativized = formula.relativize(&set).to_string();
+        assert_eq!(relativized, "∀v₁ (v₁ ∈ x → v₁ ∈ v₀)");
+    }
+
+    #[test]
+    fn relativization_does_not_capture_variables_of_the_set() {
+        let config = SetConfig::builder()
+            .variables(false)
+            .power_set(false)
+            .build();
+        assert_eq!(
+            transform("(∃y y ∈ x)^Pot(y)", &config),
+            "∃v₀ (v₀ ∈ Pot(y) ∧ v₀ ∈ x)"
+        );
+        assert_eq!(
+            transform("(∀x ∃y x ∈ y)^y", &config),
+            "∀x (x ∈ y → ∃v₀ (v₀ ∈ y ∧ x ∈ v₀))"
+        );
+    }
+
+    #[test]
+    fn substitution_renames_bound_variables_of_the_term() {
+        let formula = parse("∀y x ∈ y").unwrap().into_root();
+        let NodeType::Variable(x) = formula.children[1].children[0].entry else {unreachable!()};
+        USED_INDICES.with(|rc| rc.replace(formula.collect_used_indices(BTreeSet::new())));
+        let result = formula.substitute(x, &crate::build::var("y"));
+        assert_eq!(result.to_string(), "∀v₀ y ∈ v₀");
+    }
+
+    fn big_intersection(
+        input: &str,
+        convention: BigIntersectionDefinition,
+    ) -> (String, Vec<String>) {
+        let mut config = SetConfig::builder().variables(false).build();
+        config.profile.big_intersection = convention;
+        crate::run_with_warnings(input, config)
+    }
+
+    #[test]
+    fn big_intersection_conventions() {
+        let (output, warnings) = big_intersection("x ∈ Durchschnitt(A)", EmptySet);
+        assert_eq!(output, "(∃v₁ v₁ ∈ A ∧ ∀v₀ (v₀ ∈ A → x ∈ v₀))");
+        assert_eq!(
+            warnings,
+            ["Result would differ if Durchschnitt(∅) were the universe unless A ≠ ∅"]
+        );
+        assert_eq!(big_intersection("x ∈ Durchschnitt(A)", Undefined).0, output);
+        let (output, _) = big_intersection("x ∈ Durchschnitt(A)", Universe);
+        assert_eq!(output, "∀v₀ (v₀ ∈ A → x ∈ v₀)");
+        let (output, warnings) = big_intersection("B = Durchschnitt(A)", Undefined);
+        assert_eq!(
+            output,
+            "(∃v₁ v₁ ∈ A ∧ ∀v₀ (v₀ ∈ B ↔ ∀v₂ (v₂ ∈ A → v₀ ∈ v₂)))"
+        );
+        assert_eq!(
+            warnings,
+            ["Result would differ if Durchschnitt(∅) were ∅ or the universe unless A ≠ ∅"]
+        );
+        let (output, warnings) = big_intersection("x ∈ Durchschnitt({a, b})", Undefined);
+        assert_eq!(output, "∀v₀ ((v₀ = a ∨ v₀ = b) → x ∈ v₀)");
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn big_intersection_warnings_use_the_names_of_the_input() {
+        let config = SetConfig::default();
+        let (_, warnings) = crate::run_with_warnings("∀y y ∈ Durchschnitt(x ∪ z)", config);
+        assert_eq!(
+            warnings,
+            ["Result would differ if Durchschnitt(∅) were the universe unless x ∪ z ≠ ∅"]
+        );
+    }
+
+    #[test]
+    fn equality_by_extensionality_or_leibniz() {
+        let config = SetConfig::builder().variables(false).equality(true).build();
+        assert_eq!(transform("x = y", &config), "∀v₀ (v₀ ∈ x ↔ v₀ ∈ y)");
+        let config = SetConfig::builder()
+            .variables(false)
+            .equality(true)
+            .equality_leibniz(true)
+            .build();
+        assert_eq!(transform("x = y", &config), "∀v₀ (x ∈ v₀ ↔ y ∈ v₀)");
+    }
+
+    struct Empty;
+
+    impl Rule for Empty {
+        fn name(&self) -> &str {
+            "Leer(t)"
+        }
+
+        fn pattern(&self, node: &SyntaxNode) -> bool {
+            matches!(&node.entry, NodeType::Predicate(name) if &**name == "Leer")
+        }
+
+        fn rewrite(&self, mut node: SyntaxNode, _config: &SetConfig) -> SyntaxNode {
+            not(exists(var("y"), var("y").elem(node.children.remove(0))))
+        }
+    }
+
+    #[test]
+    fn registered_rules_apply_on_every_thread() {
+        register_rule(Empty);
+        let config = SetConfig::builder()
+            .variables(false)
+            .definition("Leer", false)
+            .build();
+        let output = std::thread::spawn(move || {
+            transform("def Leer(x) := ∀y y ∉ x; (Leer(a) ∧ Leer(b))", &config)
+        });
+        assert_eq!(
+            output.join().unwrap(),
+            "def Leer(x) := ∀y ¬y ∈ x; (¬∃y y ∈ a ∧ ¬∃y y ∈ b)"
+        );
+        assert_eq!(rule_names()[0], "Leer(t)");
+    }
+
+    #[test]
+    fn chained_operators_need_no_existentials() {
+        let config = SetConfig::builder().variables(false).build();
+        assert_eq!(
+            transform("x ∈ a ∪ b ∪ c", &config),
+            "(x ∈ a ∨ (x ∈ b ∨ x ∈ c))"
+        );
+        assert_eq!(
+            transform("x ∈ a ∩ b ∩ c", &config),
+            "(x ∈ a ∧ (x ∈ b ∧ x ∈ c))"
+        );
+    }
+
+    #[test]
+    fn symmetric_difference() {
+        let config = SetConfig::builder().variables(false).build();
+        assert_eq!(
+            transform("x ∈ a △ b", &config),
+            "((x ∈ a ∧ ¬x ∈ b) ∨ (x ∈ b ∧ ¬x ∈ a))"
+        );
+        assert_eq!(
+            transform("a △ b = c", &config),
+            "∀v₀ (((v₀ ∈ a ∧ ¬v₀ ∈ b) ∨ (v₀ ∈ b ∧ ¬v₀ ∈ a)) ↔ v₀ ∈ c)"
+        );
+    }
+
+    #[test]
+    fn enumerations_directly_or_as_pairs() {
+        let config = SetConfig::builder().variables(false).build();
+        assert_eq!(
+            transform("x ∈ {a, b, c}", &config),
+            "(x = a ∨ (x = b ∨ x = c))"
+        );
+        assert_eq!(
+            transform("{a, b, c} = y", &config),
+            "∀v₀ ((v₀ = a ∨ (v₀ = b ∨ v₀ = c)) ↔ v₀ ∈ y)"
+        );
+        let config = SetConfig::builder()
+            .variables(false)
+            .enumeration_as_pairs(true)
+            .singleton(false)
+            .pair_set(false)
+            .union(false)
+            .build();
+        assert_eq!(transform("x ∈ {a, b, c}", &config), "x ∈ {a , b} ∪ {c}");
+        assert_eq!(
+            transform("{a, b, c, d} = y", &config),
+            "{a , b} ∪ {c , d} = y"
+        );
+    }
+
+    #[test]
+    fn class_comprehensions() {
+        let config = SetConfig::builder().variables(false).build();
+        assert_eq!(transform("b ∈ {y | ¬y ∈ y}", &config), "¬b ∈ b");
+        assert_eq!(transform("y ∈ {x | ∀y x ∈ y}", &config), "∀v₀ y ∈ v₀");
+        assert_eq!(
+            transform("{y | y ∈ a} = b", &config),
+            "∀v₀ (v₀ ∈ b ↔ v₀ ∈ a)"
+        );
+        assert_eq!(
+            transform("{y | y ∈ a} ∈ b", &config),
+            "∃v₀ (∀v₁ (v₁ ∈ v₀ ↔ v₁ ∈ a) ∧ v₀ ∈ b)"
+        );
+    }
+
+    #[test]
+    fn definition_profiles() {
+        let config = |name: &str| {
+            let (_, profile) = PROFILES.iter().find(|(n, _)| *n == name).unwrap();
+            SetConfig::builder()
+                .variables(false)
+                .profile(*profile)
+                .build()
+        };
+        assert!(transform("x ∈ {y}", &config("Standard")).contains('∀'));
+        assert_eq!(transform("x ∈ {y}", &config("Pairing")), "(x = y ∨ x = y)");
+        assert_eq!(transform("x ∈ {y}", &config("Wiener")), "x = y");
+        let keep_terms = |config: SetConfig| SetConfig {
+            singleton: false,
+            pair_set: false,
+            empty_set: false,
+            ..config
+        };
+        let standard = keep_terms(config("Standard"));
+        assert_eq!(transform("x = ⟨a, b⟩", &standard), "x = {{a} , {a , b}}");
+        let wiener = keep_terms(config("Wiener"));
+        assert_eq!(transform("x = ⟨a, b⟩", &wiener), "x = {{{a} , ∅} , {{b}}}");
+    }
+}
@@ -0,0 +1,265 @@
+use crate::parser::{Connective, Constant, NodeType, Operator, Quantifier, Relation, SyntaxNode};
+
+/// How [`SyntaxNode::to_dot`] and [`SyntaxNode::to_ascii_tree`] draw a syntax tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeOptions {
+    /// Use plain ASCII instead of box-drawing characters for the branches.
+    pub ascii: bool,
+    /// Mark every variable as bound or free.
+    pub variables: bool,
+    /// Path of child indices to a subterm to highlight, e.g. from [`SyntaxNode::rewritten_from`].
+    pub highlight: Option<Vec<usize>>,
+}
+
+/// A node of the tree in preorder.
+struct Entry {
+    parent: Option<usize>,
+    last: bool,
+    label: String,
+    bound: Option<bool>,
+    highlighted: bool,
+}
+
+impl SyntaxNode {
+    /// Renders the syntax tree in the Graphviz DOT language.
+    pub fn to_dot(&self, options: &TreeOptions) -> String {
+        let mut result =
+            String::from("digraph {\n    ordering=out;\n    node [shape=plaintext];\n");
+        for (i, entry) in self.entries(options).into_iter().enumerate() {
+            let label = entry.label.replace('\\', "\\\\").replace('"', "\\\"");
+            result.push_str(&format!("    n{i} [label=\"{label}\""));
+            match entry.bound {
+                Some(true) => result.push_str(", fontcolor=blue"),
+                Some(false) => result.push_str(", fontcolor=red"),
+                None => (),
+            }
+            if entry.highlighted {
+                result.push_str(", style=filled, fillcolor=yellow");
+            }
+            result.push_str("];\n");
+            if let Some(parent) = entry.parent {
+                result.push_str(&format!("    n{parent} -> n{i};\n"));
+            }
+        }
+        result.push('}');
+        result
+    }
+
+    /// Renders the syntax tree as indented text, one node per line.
+    pub fn to_ascii_tree(&self, options: &TreeOptions) -> String {
+        let (branch, last, pipe, marker) = match options.ascii {
+            true => ("|-- ", "`-- ", "|   ", "  <- rewritten"),
+            false => ("├── ", "└── ", "│   ", "  ◀ rewritten"),
+        };
+        let entries = self.entries(options);
+        let mut lines = vec![];
+        for entry in &entries {
+            let mut parts = vec![];
+            if entry.parent.is_some() {
+                parts.push(if entry.last { last } else { branch });
+            }
+            let mut ancestor = entry.parent;
+            while let Some(a) = ancestor {
+                if entries[a].parent.is_some() {
+                    parts.push(if entries[a].last { "    " } else { pipe });
+                }
+                ancestor = entries[a].parent;
+            }
+            parts.reverse();
+            let mut line = parts.concat() + &entry.label;
+            match entry.bound {
+                Some(true) => line.push_str(" (bound)"),
+                Some(false) => line.push_str(" (free)"),
+                None => (),
+            }
+            if entry.highlighted && entry.parent.map_or(true, |p| !entries[p].highlighted) {
+                line.push_str(marker);
+            }
+            lines.push(line);
+        }
+        lines.join("\n")
+    }
+
+    /// Path of child indices to the smallest subterm of `self` that contains all differences
+    /// to `before`, such as the subterm rewritten by a single transformation step.
+    pub fn rewritten_from(&self, before: &SyntaxNode) -> Option<Vec<usize>> {
+        if self == before {
+            return None;
+        }
+        let (mut after, mut before) = (self, before);
+        let mut path = vec![];
+        while after.entry == before.entry && after.children.len() == before.children.len() {
+            let mut differences =
+                (0..after.children.len()).filter(|&i| after.children[i] != before.children[i]);
+            let (Some(i), None) = (differences.next(), differences.next()) else {break};
+            path.push(i);
+            (after, before) = (&after.children[i], &before.children[i]);
+        }
+        Some(path)
+    }
+
+    fn entries(&self, options: &TreeOptions) -> Vec<Entry> {
+        let mut entries = vec![];
+        let highlight = options.highlight.as_deref();
+        self.walk(options, highlight, &mut vec![], None, true, &mut entries);
+        entries
+    }
+
+    fn walk(
+        &self,
+        options: &TreeOptions,
+        highlight: Option<&[usize]>,
+        bound: &mut Vec<u32>,
+        parent: Option<usize>,
+        last: bool,
+        entries: &mut Vec<Entry>,
+    ) {
+        let index = entries.len();
+        entries.push(Entry {
+            parent,
+            last,
+            label: self.label(),
+            bound: match self.entry {
+                NodeType::Variable(v) if options.variables => Some(bound.contains(&v)),
+                _ => None,
+            },
+            highlighted: highlight == Some(&[]),
+        });
+        for (i, child) in self.children.iter().enumerate() {
+            let highlight = match highlight {
+                Some([]) => Some(&[][..]),
+                Some([j, rest @ ..]) if *j == i => Some(rest),
+                _ => None,
+            };
+            let len = bound.len();
+            bound.extend(self.bound_in(i));
+            let last = i + 1 == self.children.len();
+            child.walk(options, highlight, bound, Some(index), last, entries);
+            bound.truncate(len);
+        }
+    }
+
+    /// Variables bound by `self` in its `i`-th child, including the binders themselves.
+    fn bound_in(&self, i: usize) -> Vec<u32> {
+        self.bindings()
+            .into_iter()
+            .filter(|(binder, scope)| *binder == i || scope.contains(&i))
+            .map(|(binder, _)| {
+                let NodeType::Variable(v) = self.children[binder].entry else {unreachable!()};
+                v
+            })
+            .collect()
+    }
+
+    /// Short name of the node's type as shown in the tree renderings, e.g. `∀` or `Pot`.
+    pub fn label(&self) -> String {
+        let label = match &self.entry {
+            NodeType::Variable(..) => return self.to_string(),
+            NodeType::Definition(name) => return format!("def {name}"),
+            NodeType::Operation(name) | NodeType::Predicate(name) => return name.to_string(),
+            NodeType::Constant(Constant::EmptySet) => "∅",
+            NodeType::Constant(Constant::Omega) => "ω",
+            NodeType::Comprehension => "{· ∈ · | ·}",
+            NodeType::ClassComprehension => "{· | ·}",
+            NodeType::Replacement => "{· | · ∈ ·}",
+            NodeType::Description => "ι",
+            NodeType::Relativization => "(·)^·",
+            NodeType::Relation(r) => match *r {
+                Relation::Equality => "=",
+                Relation::Element => "∈",
+                Relation::Subset => "⊆",
+                Relation::NotEqual => "≠",
+                Relation::NotElement => "∉",
+                Relation::NotSubset => "⊈",
+            },
+            NodeType::Operator(o) => match *o {
+                Operator::Singleton => "{·}",
+                Operator::PowerSet => "Pot",
+                Operator::BigUnion => "Vereinigung",
+                Operator::BigIntersection => "Durchschnitt",
+                Operator::Union => "∪",
+                Operator::Intersection => "∩",
+                Operator::Difference => "\\",
+                Operator::OrderedPair => "⟨· , ·⟩",
+                Operator::SymmetricDifference => "△",
+                Operator::PairSet => "{· , ·}",
+                Operator::Enumeration => "{· , … , ·}",
+            },
+            NodeType::Connective(c) => match *c {
+                Connective::Negation => "¬",
+                Connective::Conjunction => "∧",
+                Connective::Disjunction => "∨",
+                Connective::Implication => "→",
+                Connective::Biconditional => "↔",
+            },
+            NodeType::Quantifier(q) => match *q {
+                Quantifier::Universal => "∀",
+                Quantifier::Existential => "∃",
+                Quantifier::UniqueExistential => "∃!",
+            },
+            NodeType::BoundedQuantifier(q) => match *q {
+                Quantifier::Universal => "∀ ∈",
+                Quantifier::Existential => "∃ ∈",
+                Quantifier::UniqueExistential => "∃! ∈",
+            },
+        };
+        label.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TreeOptions;
+    use crate::{parse, SetConfig, Strategy};
+
+    #[test]
+    fn ascii_tree_marks_variables_and_the_highlighted_subterm() {
+        let formula = parse("∀x (x ∈ y ∧ ¬x = y)").unwrap();
+        let options = TreeOptions {
+            ascii: true,
+            variables: true,
+            highlight: Some(vec![1, 1]),
+        };
+        let expected = [
+            "∀",
+            "|-- x (bound)",
+            "`-- ∧",
+            "    |-- ∈",
+            "    |   |-- x (bound)",
+            "    |   `-- y (free)",
+            "    `-- ¬  <- rewritten",
+            "        `-- =",
+            "            |-- x (bound)",
+            "            `-- y (free)",
+        ];
+        assert_eq!(formula.to_ascii_tree(&options), expected.join("\n"));
+        let plain = formula.to_ascii_tree(&TreeOptions::default());
+        assert!(plain.starts_with("∀\n├── x\n└── ∧\n"));
+    }
+
+    #[test]
+    fn dot_colours_variables_and_fills_the_highlighted_subterm() {
+        let options = TreeOptions {
+            variables: true,
+            highlight: Some(vec![1]),
+            ..TreeOptions::default()
+        };
+        let dot = parse("∃x x ∈ y").unwrap().to_dot(&options);
+        assert!(dot.starts_with("digraph {\n"));
+        assert!(dot.contains("n1 [label=\"x\", fontcolor=blue];\n    n0 -> n1;"));
+        assert!(dot.contains("n2 [label=\"∈\", style=filled, fillcolor=yellow];"));
+        assert!(dot.contains("n4 [label=\"y\", fontcolor=red, style=filled, fillcolor=yellow];"));
+    }
+
+    #[test]
+    fn rewritten_from_finds_the_single_step() {
+        let config = SetConfig::builder()
+            .variables(false)
+            .strategy(Strategy::SingleStep)
+            .build();
+        let before = parse("(x ∈ a ∧ y ⊆ b)").unwrap();
+        let after = before.transform(&config).unwrap();
+        assert_eq!(after.root().rewritten_from(before.root()), Some(vec![1]));
+        assert_eq!(before.root().rewritten_from(before.root()), None);
+    }
+}
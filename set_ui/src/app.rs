@@ -1,40 +1,146 @@
+use egui::text::LayoutJob;
 use egui::FontData;
 use egui::FontDefinitions;
 use egui::FontFamily;
-use egui::RichText;
+use egui::TextFormat;
+use std::ops::Range;
 
+#[derive(Default)]
 pub struct SetUI {
     input: String,
     output: String,
+    warnings: Vec<String>,
+    classification: String,
+    metrics: String,
+    relativization: String,
+    config_file: String,
+    trees: [Option<Tree>; 2],
+    hovered: Option<(usize, usize)>,
 
     config: set::SetConfig,
 }
 
-impl Default for SetUI {
-    fn default() -> Self {
-        Self {
-            input: String::new(),
-            output: String::new(),
-            config: set::SetConfig {
-                variables: true,
-                empty_set: true,
-                omega: true,
-                negated_relations: true,
-                subset: true,
-                singleton: true,
-                comprehension: true,
-                power_set: true,
-                big_intersection: true,
-                big_union: true,
-                intersection: true,
-                difference: true,
-                union: true,
-                pair_set: true,
-            },
+/// A parsed input or output together with its text and the span of each node in preorder.
+struct Tree {
+    formula: set::Formula,
+    text: String,
+    spans: Vec<Option<Range<usize>>>,
+}
+
+impl Tree {
+    fn parse(input: &str) -> Option<Self> {
+        let formula = set::parse(input).ok()?;
+        let (text, spans) = formula.root().to_string_with_spans();
+        Some(Tree {
+            formula,
+            text,
+            spans,
+        })
+    }
+}
+
+/// State of one tree while it is drawn in the tree view.
+struct TreeView {
+    tree: usize,
+    hovered: Option<(usize, usize)>,
+    unfold: Option<(usize, Vec<usize>)>,
+}
+
+impl TreeView {
+    fn show(
+        &mut self,
+        ui: &mut egui::Ui,
+        node: &set::SyntaxNode,
+        parent: Option<&set::SyntaxNode>,
+        path: &mut Vec<usize>,
+        index: &mut usize,
+    ) {
+        let i = *index;
+        let eliminable = eliminable(node, parent);
+        let header = |ui: &mut egui::Ui| {
+            let hovered = ui.label(node.label()).hovered();
+            let unfold = eliminable && ui.small_button("Eliminate").clicked();
+            (hovered, unfold)
+        };
+        let (hovered, unfold) = if node.children.is_empty() {
+            header(ui)
+        } else {
+            let id = ui.make_persistent_id((self.tree, path.clone()));
+            let state = egui::collapsing_header::CollapsingState::load_with_default_open(
+                ui.ctx(),
+                id,
+                path.len() < 3,
+            );
+            let (_, header, _) = state.show_header(ui, header).body(|ui| {
+                *index += 1;
+                for (c, child) in node.children.iter().enumerate() {
+                    path.push(c);
+                    self.show(ui, child, Some(node), path, index);
+                    path.pop();
+                }
+            });
+            header.inner
+        };
+        if hovered {
+            self.hovered = Some((self.tree, i));
+        }
+        if unfold {
+            self.unfold = Some((self.tree, path.clone()));
         }
+        *index = i + node.nodes();
+    }
+}
+
+/// Whether [`set::Formula::unfold_at`] can eliminate `node`. Operands are eliminated by
+/// rewriting the relation they appear in.
+fn eliminable(node: &set::SyntaxNode, parent: Option<&set::SyntaxNode>) -> bool {
+    use set::{NodeType, Quantifier, Relation};
+    match node.entry {
+        NodeType::Operation(..)
+        | NodeType::Predicate(..)
+        | NodeType::Relativization
+        | NodeType::BoundedQuantifier(..)
+        | NodeType::Quantifier(Quantifier::UniqueExistential)
+        | NodeType::Relation(
+            Relation::Subset | Relation::NotEqual | Relation::NotElement | Relation::NotSubset,
+        ) => true,
+        NodeType::Operator(..)
+        | NodeType::Comprehension
+        | NodeType::Replacement
+        | NodeType::ClassComprehension
+        | NodeType::Description => matches!(
+            parent.map(|parent| &parent.entry),
+            Some(NodeType::Relation(Relation::Equality | Relation::Element))
+        ),
+        _ => false,
     }
 }
 
+/// Lays out `text` with the byte range `span` highlighted.
+fn highlighted(ui: &egui::Ui, text: &str, span: Option<Range<usize>>, strong: bool) -> LayoutJob {
+    let format = TextFormat {
+        font_id: egui::TextStyle::Body.resolve(ui.style()),
+        color: match strong {
+            true => ui.visuals().strong_text_color(),
+            false => ui.visuals().text_color(),
+        },
+        ..Default::default()
+    };
+    let span = span.unwrap_or(0..0);
+    let mut job = LayoutJob::default();
+    job.append(&text[..span.start], 0.0, format.clone());
+    job.append(
+        &text[span.clone()],
+        0.0,
+        TextFormat {
+            background: ui.visuals().selection.bg_fill,
+            ..format.clone()
+        },
+    );
+    job.append(&text[span.end..], 0.0, format);
+    job
+}
+
 impl SetUI {
     pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         let mut fonts = FontDefinitions::default();
@@ -58,6 +164,13 @@ impl eframe::App for SetUI {
         let Self {
             input,
             output,
+            warnings,
+            classification,
+            metrics,
+            relativization,
+            config_file,
+            trees,
+            hovered,
             config,
         } = self;
         egui::Window::new("set").collapsible(false).show(ctx, |ui| {
@@ -76,6 +189,48 @@ impl eframe::App for SetUI {
                     )
                     .request_focus();
                     ui.end_row();
+                    ui.label("Preset");
+                    let preset = set::PRESETS
+                        .iter()
+                        .find(|(_, preset)| preset() == *config)
+                        .map_or("Custom", |(name, _)| name);
+                    egui::ComboBox::from_id_source("preset")
+                        .selected_text(preset)
+                        .show_ui(ui, |ui| {
+                            for (name, preset) in set::PRESETS {
+                                if ui.selectable_label(false, *name).clicked() {
+                                    *config = preset();
+                                    relativization.clear();
+                                }
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Config file");
+                    ui.horizontal(|ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(config_file)
+                                .desired_rows(1)
+                                .hint_text("TOML")
+                                .code_editor(),
+                        );
+                        if ui.button("Load").clicked() {
+                            match set::SetConfig::from_toml(config_file) {
+                                Ok(loaded) => {
+                                    *relativization =
+                                        loaded.relativization.clone().unwrap_or_default();
+                                    *config = loaded;
+                                }
+                                Err(e) => *output = e.to_string(),
+                            }
+                        }
+                        if ui.button("Save").clicked() {
+                            match config.to_toml() {
+                                Ok(toml) => *config_file = toml,
+                                Err(e) => *output = e.to_string(),
+                            }
+                        }
+                    });
+                    ui.end_row();
                     ui.label("Eliminate");
                     egui::Grid::new("options_grid").show(ui, |ui| {
                         ui.checkbox(&mut config.negated_relations, "≠ ∉ ⊈");
@@ -104,23 +259,185 @@ impl eframe::App for SetUI {
 
                         ui.checkbox(&mut config.empty_set, "∅");
                         ui.checkbox(&mut config.omega, "ω");
+                        ui.checkbox(&mut config.symmetric_difference, "Symmetric difference");
+
+                        ui.end_row();
+
+                        ui.checkbox(&mut config.enumeration, "Enumeration");
+                        ui.checkbox(&mut config.enumeration_as_pairs, "Enumeration via pairs");
+                        ui.checkbox(&mut config.replacement, "Replacement");
+
+                        ui.end_row();
+
+                        ui.checkbox(&mut config.class_comprehension, "Class term");
+                        ui.checkbox(&mut config.bounded_quantifiers, "Bounded quantifiers");
+                        ui.checkbox(&mut config.unique_existence, "∃!");
+
+                        ui.end_row();
+
+                        ui.checkbox(&mut config.description, "ι");
+                        ui.checkbox(&mut config.equality, "=");
+                        ui.checkbox(&mut config.equality_leibniz, "= via Leibniz");
+
+                        ui.end_row();
+
+                        ui.checkbox(&mut config.ordered_pair, "Ordered pair");
+                    });
+                    ui.end_row();
+                    let symbols = set::defined_symbols(input);
+                    if !symbols.is_empty() {
+                        ui.label("Definitions");
+                        egui::Grid::new("definitions_grid").show(ui, |ui| {
+                            for (i, name) in symbols.into_iter().enumerate() {
+                                if i > 0 && i % 3 == 0 {
+                                    ui.end_row();
+                                }
+                                let unfold = config.definitions.entry(name.clone()).or_insert(true);
+                                ui.checkbox(unfold, name);
+                            }
+                        });
+                        ui.end_row();
+                    }
+                    ui.label("Profile");
+                    let profile = set::PROFILES
+                        .iter()
+                        .find(|(_, p)| *p == config.profile)
+                        .map_or("Custom", |(name, _)| name);
+                    egui::ComboBox::from_id_source("profile")
+                        .selected_text(profile)
+                        .show_ui(ui, |ui| {
+                            for (name, p) in set::PROFILES {
+                                ui.selectable_value(&mut config.profile, *p, *name);
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Durchschnitt(∅)");
+                    ui.horizontal(|ui| {
+                        use set::BigIntersectionDefinition as Convention;
+                        let convention = &mut config.profile.big_intersection;
+                        ui.radio_value(convention, Convention::Undefined, "Undefined");
+                        ui.radio_value(convention, Convention::EmptySet, "∅");
+                        ui.radio_value(convention, Convention::Universe, "Universe");
+                    });
+                    ui.end_row();
+                    ui.label("Relativize to");
+                    ui.add(egui::TextEdit::singleline(relativization).desired_width(f32::INFINITY));
+                    ui.end_row();
+                    ui.label("Strategy");
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut config.strategy, set::Strategy::Outermost, "Outermost");
+                        ui.radio_value(&mut config.strategy, set::Strategy::Innermost, "Innermost");
+                        ui.radio_value(
+                            &mut config.strategy,
+                            set::Strategy::SingleStep,
+                            "Single step",
+                        );
+                    });
+                    ui.end_row();
+                    ui.label("Limits");
+                    ui.horizontal(|ui| {
+                        let limits = &mut config.limits;
+                        ui.add(egui::DragValue::new(&mut limits.max_nodes).prefix("nodes "));
+                        ui.add(egui::DragValue::new(&mut limits.max_depth).prefix("depth "));
+                        ui.add(egui::DragValue::new(&mut limits.max_steps).prefix("steps "));
                     });
                     ui.end_row();
                     ui.label("Output");
-                    ui.add(egui::Label::new(RichText::new(output.clone()).strong()).wrap(true));
+                    let span = match (&trees[1], *hovered) {
+                        (Some(tree), Some((1, node))) if tree.text == *output => {
+                            tree.spans[node].clone()
+                        }
+                        _ => None,
+                    };
+                    ui.add(egui::Label::new(highlighted(ui, output, span, true)).wrap(true));
+                    if !classification.is_empty() {
+                        ui.end_row();
+                        ui.label("Lévy");
+                        ui.label(classification.as_str());
+                    }
+                    if !metrics.is_empty() {
+                        ui.end_row();
+                        ui.label("Metrics");
+                        ui.label(metrics.as_str());
+                    }
+                    if !warnings.is_empty() {
+                        ui.end_row();
+                        ui.label("Warnings");
+                        ui.vertical(|ui| {
+                            for warning in warnings.iter() {
+                                ui.label(warning);
+                            }
+                        });
+                    }
                 });
 
             ui.separator();
 
             ui.vertical_centered(|ui| {
+                config.relativization = match relativization.trim() {
+                    "" => None,
+                    set => Some(set.into()),
+                };
                 if ui.button("Transform").clicked() {
-                    *output = set::run(input, *config);
+                    (*output, *warnings) = set::run_with_warnings(input, config.clone());
+                    *classification = set::classify(input, config.clone());
+                    *metrics = set::metrics(input, config.clone());
+                    *trees = [Tree::parse(input), Tree::parse(output)];
+                }
+                if config.strategy == set::Strategy::SingleStep
+                    && !output.is_empty()
+                    && ui.button("Next step").clicked()
+                {
+                    *input = output.clone();
+                    (*output, *warnings) = set::run_with_warnings(input, config.clone());
+                    *classification = set::classify(input, config.clone());
+                    *metrics = set::metrics(input, config.clone());
+                    *trees = [Tree::parse(input), Tree::parse(output)];
                 }
 
                 egui::warn_if_debug_build(ui);
             });
         });
 
+        egui::Window::new("Tree")
+            .default_open(false)
+            .vscroll(true)
+            .show(ctx, |ui| {
+                let mut view = TreeView {
+                    tree: 0,
+                    hovered: None,
+                    unfold: None,
+                };
+                for (t, name) in ["Input", "Output"].into_iter().enumerate() {
+                    let Some(tree) = &trees[t] else {continue};
+                    egui::CollapsingHeader::new(name)
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            let span = match *hovered {
+                                Some((h, node)) if h == t => tree.spans[node].clone(),
+                                _ => None,
+                            };
+                            ui.add(
+                                egui::Label::new(highlighted(ui, &tree.text, span, false))
+                                    .wrap(true),
+                            );
+                            view.tree = t;
+                            view.show(ui, tree.formula.root(), None, &mut vec![], &mut 0);
+                        });
+                }
+                *hovered = view.hovered;
+                if let Some((t, path)) = view.unfold {
+                    let formula = trees[t]
+                        .as_ref()
+                        .and_then(|tree| tree.formula.unfold_at(&path, config));
+                    if let Some(formula) = formula {
+                        let text = if t == 0 { &mut *input } else { &mut *output };
+                        *text = formula.to_string();
+                        trees[t] = Tree::parse(text);
+                    }
+                }
+            });
+
         egui::Window::new("Symbols")
             .default_open(false)
             .show(ctx, |ui| {
@@ -182,6 +499,21 @@ impl eframe::App for SetUI {
                     if ui.button("↔").clicked() {
                         input.push('↔');
                     }
+
+                    ui.end_row();
+
+                    if ui.button("△").clicked() {
+                        input.push('△');
+                    }
+                    if ui.button("∃!").clicked() {
+                        input.push_str("∃!");
+                    }
+                    if ui.button("ι").clicked() {
+                        input.push('ι');
+                    }
+                    if ui.button("⟨⟩").clicked() {
+                        input.push_str("⟨⟩");
+                    }
                 });
             });
     }